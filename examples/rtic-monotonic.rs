@@ -0,0 +1,61 @@
+//! Blinks the LED on PD12 every second with a task scheduled on the TIM2 monotonic clock.
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![no_std]
+#![no_main]
+
+extern crate rtic;
+
+use panic_halt as _;
+use rtic::app;
+use stm32f4xx_hal::{
+    gpio::{gpiod::PD12, Output, PushPull},
+    monotonic::{Duration, MonoTimer64},
+    pac::TIM2,
+    prelude::*,
+};
+
+// The timer counts at 1 MHz
+const PERIOD: Duration = Duration::from_ticks(1_000_000);
+
+#[app(
+    device = stm32f4xx_hal::pac,
+    peripherals = true,
+    monotonic = stm32f4xx_hal::monotonic::MonoTimer64<stm32f4xx_hal::pac::TIM2>
+)]
+const APP: () = {
+    struct Resources {
+        led: PD12<Output<PushPull>>,
+        mono: MonoTimer64<TIM2>,
+    }
+
+    #[init(schedule = [blink])]
+    fn init(cx: init::Context) -> init::LateResources {
+        let rcc = cx.device.RCC.constrain();
+        let clocks = rcc.cfgr.sysclk(168.mhz()).freeze();
+
+        let gpiod = cx.device.GPIOD.split();
+        let led = gpiod.pd12.into_push_pull_output();
+
+        let mono = MonoTimer64::tim2(cx.device.TIM2, clocks, 1.mhz());
+
+        cx.schedule.blink(cx.start + PERIOD).unwrap();
+
+        init::LateResources { led, mono }
+    }
+
+    #[task(binds = TIM2, priority = 2, resources = [mono])]
+    fn tim2(cx: tim2::Context) {
+        cx.resources.mono.on_interrupt();
+    }
+
+    #[task(schedule = [blink], resources = [led])]
+    fn blink(cx: blink::Context) {
+        cx.resources.led.toggle().unwrap();
+        cx.schedule.blink(cx.scheduled + PERIOD).unwrap();
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
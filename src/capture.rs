@@ -0,0 +1,743 @@
+//! # Input capture
+//!
+//! [`Capture`] latches the counter value into the capture/compare registers on edges of the
+//! channel inputs. [`PwmInput`] measures the period and pulse width of a PWM signal on channel 1,
+//! using the slave controller to reset the counter on every rising edge.
+//!
+//! ```ignore
+//! let gpioa = dp.GPIOA.split();
+//! let pin = gpioa.pa8.into_alternate_af1();
+//!
+//! // Count at 1 MHz
+//! let pwm_input = PwmInput::new(dp.TIM1, pin, clocks, 1.mhz());
+//! let frequency = pwm_input.frequency();
+//! let duty_cycle = pwm_input.duty_cycle();
+//! ```
+use cast::u16;
+
+use crate::{bb, pac::RCC, pwm::Pins, rcc::Clocks, time::Hertz, timer::PinC1};
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::stm32::{TIM1, TIM11, TIM5, TIM9};
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::stm32::{TIM10, TIM2, TIM3, TIM4};
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::stm32::{TIM12, TIM13, TIM14, TIM8};
+
+/// Capture error
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Error {
+    /// A new value has been captured before the previous one was read, the previous value is lost
+    Overcapture,
+}
+
+/// Capture/compare channel
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Channel {
+    C1,
+    C2,
+    C3,
+    C4,
+}
+
+impl Channel {
+    fn index(self) -> u8 {
+        match self {
+            Channel::C1 => 0,
+            Channel::C2 => 1,
+            Channel::C3 => 2,
+            Channel::C4 => 3,
+        }
+    }
+}
+
+/// Edge of the input that triggers a capture
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Polarity {
+    RisingEdge,
+    FallingEdge,
+    BothEdges,
+}
+
+/// Number of events needed for a capture
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Prescaler {
+    /// Capture on every edge
+    Div1,
+    /// Capture on every 2nd edge
+    Div2,
+    /// Capture on every 4th edge
+    Div4,
+    /// Capture on every 8th edge
+    Div8,
+}
+
+/// Input capture channel configuration
+#[derive(Debug, Copy, Clone)]
+pub struct Config {
+    pub(crate) polarity: Polarity,
+    pub(crate) filter: u8,
+    pub(crate) prescaler: Prescaler,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            polarity: Polarity::RisingEdge,
+            filter: 0,
+            prescaler: Prescaler::Div1,
+        }
+    }
+}
+
+impl Config {
+    /// Edge that triggers a capture
+    pub fn polarity(mut self, polarity: Polarity) -> Self {
+        self.polarity = polarity;
+        self
+    }
+
+    /// Digital input filter, the `ICxF` value from 0 (no filter) to 15
+    ///
+    /// Panics if `filter` is above 15.
+    pub fn filter(mut self, filter: u8) -> Self {
+        assert!(filter < 16);
+        self.filter = filter;
+        self
+    }
+
+    /// Number of edges needed for a capture
+    pub fn prescaler(mut self, prescaler: Prescaler) -> Self {
+        self.prescaler = prescaler;
+        self
+    }
+
+    /// Returns the `CCMRx` byte of the channel
+    fn ccmr_bits(&self) -> u32 {
+        let psc = match self.prescaler {
+            Prescaler::Div1 => 0b00,
+            Prescaler::Div2 => 0b01,
+            Prescaler::Div4 => 0b10,
+            Prescaler::Div8 => 0b11,
+        };
+        // CCxS = 01, the channel is mapped on its own input
+        (self.filter as u32) << 4 | psc << 2 | 0b01
+    }
+
+    /// Returns the `CCxP` and `CCxNP` bits of the channel
+    fn ccer_bits(&self) -> u32 {
+        match self.polarity {
+            Polarity::RisingEdge => 0b0000,
+            Polarity::FallingEdge => 0b0010,
+            Polarity::BothEdges => 0b1010,
+        }
+    }
+}
+
+/// Input capture on the channels of a timer
+pub struct Capture<TIM, PINS> {
+    tim: TIM,
+    pins: PINS,
+    frequency: Hertz,
+}
+
+impl<TIM: Instance, PINS> Capture<TIM, PINS> {
+    /// Configures a TIM peripheral for input capture with the counter running at `freq`
+    ///
+    /// The channels of `pins` are configured to capture on rising edges and enabled.
+    pub fn new<P, T>(tim: TIM, pins: PINS, clocks: Clocks, freq: T) -> Self
+    where
+        PINS: Pins<TIM, P>,
+        T: Into<Hertz>,
+    {
+        TIM::setup_clocks();
+
+        let frequency = tim.start(TIM::timer_clock(&clocks), freq.into());
+        let mut capture = Capture {
+            tim,
+            pins,
+            frequency,
+        };
+
+        for &(used, channel) in &[
+            (PINS::C1, Channel::C1),
+            (PINS::C2, Channel::C2),
+            (PINS::C3, Channel::C3),
+            (PINS::C4, Channel::C4),
+        ] {
+            if used {
+                capture.configure(channel, Config::default());
+            }
+        }
+
+        capture
+    }
+
+    /// Configures and enables a channel
+    ///
+    /// Panics if the timer doesn't have the channel.
+    pub fn configure(&mut self, channel: Channel, config: Config) {
+        self.tim.configure_channel(channel, &config);
+    }
+
+    /// Enables capturing on a channel
+    pub fn enable(&mut self, channel: Channel) {
+        self.tim.set_ccer_bit(4 * channel.index(), true);
+    }
+
+    /// Disables capturing on a channel
+    pub fn disable(&mut self, channel: Channel) {
+        self.tim.set_ccer_bit(4 * channel.index(), false);
+    }
+
+    /// Reads the last captured counter value of a channel
+    ///
+    /// Returns `WouldBlock` if nothing has been captured since the last read.
+    pub fn read(&mut self, channel: Channel) -> nb::Result<u32, Error> {
+        let sr = self.tim.status();
+        let index = channel.index();
+        if sr & (1 << (1 + index)) == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        // Reading the capture register clears CCxIF
+        let value = self.tim.read_ccr(channel);
+        if sr & (1 << (9 + index)) != 0 {
+            self.tim.clear_status(1 << (9 + index));
+            return Err(nb::Error::Other(Error::Overcapture));
+        }
+
+        Ok(value)
+    }
+
+    /// Returns true if a value has been captured since the last read
+    pub fn is_captured(&self, channel: Channel) -> bool {
+        self.tim.status() & (1 << (1 + channel.index())) != 0
+    }
+
+    /// Returns the current counter value
+    pub fn count(&self) -> u32 {
+        self.tim.count()
+    }
+
+    /// Returns the frequency the counter is running at
+    pub fn frequency(&self) -> Hertz {
+        self.frequency
+    }
+
+    /// Starts listening for captures on a channel
+    ///
+    /// Reading the captured value clears the interrupt.
+    pub fn listen(&mut self, channel: Channel) {
+        self.tim.set_dier_bit(1 + channel.index(), true);
+    }
+
+    /// Stops listening for captures on a channel
+    pub fn unlisten(&mut self, channel: Channel) {
+        self.tim.set_dier_bit(1 + channel.index(), false);
+    }
+
+    /// Issues a DMA request on every capture of a channel
+    ///
+    /// The captured values are transferred by using the `CCRx` wrappers of [`crate::dma::traits`]
+    /// with the timer returned by [`release`](Self::release).
+    pub fn enable_dma(&mut self, channel: Channel) {
+        self.tim.set_dier_bit(9 + channel.index(), true);
+    }
+
+    /// Stops issuing DMA requests for a channel
+    pub fn disable_dma(&mut self, channel: Channel) {
+        self.tim.set_dier_bit(9 + channel.index(), false);
+    }
+
+    /// Releases the TIM peripheral and pins, the timer keeps running
+    pub fn release(self) -> (TIM, PINS) {
+        (self.tim, self.pins)
+    }
+}
+
+/// PWM input mode, measuring the period and pulse width of a signal on channel 1
+///
+/// Channel 1 captures the period on rising edges, channel 2 the pulse width on falling edges of
+/// the same input. The counter is reset on every rising edge, so the signal period must be
+/// shorter than the counter range, i.e. 65536 ticks for 16-bit timers. A counter overflow marks
+/// the signal as stopped, see [`PwmInput::is_stopped`].
+pub struct PwmInput<TIM, PIN> {
+    tim: TIM,
+    pin: PIN,
+    frequency: Hertz,
+}
+
+impl<TIM: SlaveInstance, PIN> PwmInput<TIM, PIN> {
+    /// Configures a TIM peripheral for PWM input with the counter running at `freq`
+    pub fn new<T>(tim: TIM, pin: PIN, clocks: Clocks, freq: T) -> Self
+    where
+        PIN: PinC1<TIM>,
+        T: Into<Hertz>,
+    {
+        TIM::setup_clocks();
+
+        let frequency = tim.start(TIM::timer_clock(&clocks), freq.into());
+        tim.setup_pwm_input();
+
+        PwmInput {
+            tim,
+            pin,
+            frequency,
+        }
+    }
+
+    /// Returns the last measured period in counter ticks
+    pub fn period_ticks(&self) -> u32 {
+        self.tim.read_ccr(Channel::C1)
+    }
+
+    /// Returns the last measured pulse width in counter ticks
+    pub fn pulse_ticks(&self) -> u32 {
+        self.tim.read_ccr(Channel::C2)
+    }
+
+    /// Returns true if no rising edge was seen for a whole counter range since the last captured
+    /// period, i.e. the signal stopped or its period is too long to measure
+    ///
+    /// The period and pulse width registers keep the last captured values in that case.
+    pub fn is_stopped(&self) -> bool {
+        self.tim.overflowed_since_capture()
+    }
+
+    /// Returns the frequency of the signal, `None` if no period has been measured yet or the
+    /// signal stopped
+    pub fn frequency(&self) -> Option<Hertz> {
+        if self.is_stopped() {
+            return None;
+        }
+        match self.period_ticks() {
+            0 => None,
+            period => Some(Hertz(self.frequency.0 / period)),
+        }
+    }
+
+    /// Returns the duty cycle of the signal as a ratio from 0.0 to 1.0, `None` if no period has
+    /// been measured yet or the signal stopped
+    pub fn duty_cycle(&self) -> Option<f32> {
+        if self.is_stopped() {
+            return None;
+        }
+        match self.period_ticks() {
+            0 => None,
+            period => Some(self.pulse_ticks() as f32 / period as f32),
+        }
+    }
+
+    /// Returns the frequency the counter is running at
+    pub fn counter_frequency(&self) -> Hertz {
+        self.frequency
+    }
+
+    /// Releases the TIM peripheral and pin
+    pub fn release(self) -> (TIM, PIN) {
+        // pause counter
+        self.tim.stop();
+        (self.tim, self.pin)
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Timers with input capture channels
+pub trait Instance: sealed::Sealed {
+    #[doc(hidden)]
+    fn setup_clocks();
+    #[doc(hidden)]
+    fn timer_clock(clocks: &Clocks) -> Hertz;
+    #[doc(hidden)]
+    fn start(&self, clk: Hertz, freq: Hertz) -> Hertz;
+    #[doc(hidden)]
+    fn stop(&self);
+    #[doc(hidden)]
+    fn configure_channel(&self, channel: Channel, config: &Config);
+    #[doc(hidden)]
+    fn set_ccer_bit(&self, bit: u8, set: bool);
+    #[doc(hidden)]
+    fn set_dier_bit(&self, bit: u8, set: bool);
+    #[doc(hidden)]
+    fn status(&self) -> u32;
+    #[doc(hidden)]
+    fn clear_status(&self, mask: u32);
+    #[doc(hidden)]
+    fn read_ccr(&self, channel: Channel) -> u32;
+    #[doc(hidden)]
+    fn count(&self) -> u32;
+}
+
+/// Timers with a slave mode controller, which can measure PWM input
+pub trait SlaveInstance: Instance {
+    #[doc(hidden)]
+    fn setup_pwm_input(&self);
+    #[doc(hidden)]
+    fn overflowed_since_capture(&self) -> bool;
+}
+
+macro_rules! hal {
+    ($($TIM:ident: ($en_bit:expr, $reset_bit:expr, $apbenr:ident, $apbrstr:ident, $pclk:ident, $ppre:ident, [$($C:ident: ($ccmr:ident, $ccr:ident)),+ $(,)*]),)+) => {
+        $(
+            impl sealed::Sealed for $TIM {}
+            impl Instance for $TIM {
+                fn setup_clocks() {
+                    unsafe {
+                        // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+                        let rcc = &(*RCC::ptr());
+                        // Enable and reset clock.
+                        bb::set(&rcc.$apbenr, $en_bit);
+                        // Stall the pipeline to work around erratum 2.1.13 (DM00037591)
+                        cortex_m::asm::dsb();
+                        bb::set(&rcc.$apbrstr, $reset_bit);
+                        bb::clear(&rcc.$apbrstr, $reset_bit);
+                    }
+                }
+
+                fn timer_clock(clocks: &Clocks) -> Hertz {
+                    Hertz(clocks.$pclk().0 * if clocks.$ppre() == 1 { 1 } else { 2 })
+                }
+
+                fn start(&self, clk: Hertz, freq: Hertz) -> Hertz {
+                    let psc = u16(clk.0 / freq.0 - 1).unwrap();
+                    self.psc.write(|w| w.psc().bits(psc));
+                    // count over the whole range, 16-bit timers ignore the upper half
+                    self.arr.write(|w| unsafe { w.bits(u32::MAX) });
+
+                    // Trigger update event to load the registers
+                    self.cr1.modify(|_, w| w.urs().set_bit());
+                    self.egr.write(|w| w.ug().set_bit());
+                    self.cr1.modify(|_, w| w.urs().clear_bit());
+
+                    self.cr1.modify(|_, w| w.cen().set_bit());
+
+                    Hertz(clk.0 / (psc as u32 + 1))
+                }
+
+                fn stop(&self) {
+                    self.cr1.modify(|_, w| w.cen().clear_bit());
+                }
+
+                fn configure_channel(&self, channel: Channel, config: &Config) {
+                    let index = channel.index();
+                    // The channel has to be disabled while CCxS is written
+                    self.set_ccer_bit(4 * index, false);
+
+                    let offset = 8 * (index & 1);
+                    match channel {
+                        $(
+                            Channel::$C => self.$ccmr().modify(|r, w| unsafe {
+                                w.bits((r.bits() & !(0xff << offset)) | (config.ccmr_bits() << offset))
+                            }),
+                        )+
+                        #[allow(unreachable_patterns)]
+                        _ => panic!("timer has no channel {:?}", channel),
+                    }
+
+                    let offset = 4 * index;
+                    self.ccer.modify(|r, w| unsafe {
+                        w.bits((r.bits() & !(0b1111 << offset)) | ((config.ccer_bits() | 1) << offset))
+                    });
+                }
+
+                fn set_ccer_bit(&self, bit: u8, set: bool) {
+                    unsafe { bb::write(&self.ccer, bit, set) }
+                }
+
+                fn set_dier_bit(&self, bit: u8, set: bool) {
+                    unsafe { bb::write(&self.dier, bit, set) }
+                }
+
+                fn status(&self) -> u32 {
+                    self.sr.read().bits()
+                }
+
+                fn clear_status(&self, mask: u32) {
+                    // the flags are cleared by writing 0, writing 1 has no effect
+                    self.sr.write(|w| unsafe { w.bits(!mask) });
+                }
+
+                fn read_ccr(&self, channel: Channel) -> u32 {
+                    match channel {
+                        $(
+                            Channel::$C => self.$ccr.read().bits(),
+                        )+
+                        #[allow(unreachable_patterns)]
+                        _ => panic!("timer has no channel {:?}", channel),
+                    }
+                }
+
+                fn count(&self) -> u32 {
+                    self.cnt.read().bits()
+                }
+            }
+        )+
+    }
+}
+
+macro_rules! slave {
+    ($($TIM:ident,)+) => {
+        $(
+            impl SlaveInstance for $TIM {
+                fn setup_pwm_input(&self) {
+                    // CC1S = 01, IC1 is mapped on TI1
+                    // CC2S = 10, IC2 is mapped on TI1 as well
+                    self.ccmr1_input()
+                        .write(|w| unsafe { w.bits(0b01 | 0b10 << 8) });
+                    // IC1 captures the period on rising edges, IC2 the pulse on falling edges
+                    self.ccer.write(|w| unsafe { w.bits(0b0001 | 0b0011 << 4) });
+                    // TS = 101, trigger on TI1FP1
+                    // SMS = 100, reset the counter on the trigger
+                    self.smcr.write(|w| unsafe { w.bits(0b101 << 4 | 0b100) });
+                    // Only overflows set UIF, not the counter resets by the trigger
+                    self.cr1.modify(|_, w| w.urs().set_bit());
+                    self.sr.write(|w| unsafe { w.bits(0) });
+                }
+
+                fn overflowed_since_capture(&self) -> bool {
+                    let sr = self.sr.read().bits();
+                    if sr & (1 << 1) != 0 {
+                        // A period was captured and the counter reset since the last check
+                        self.sr.write(|w| unsafe { w.bits(!(1 | 1 << 1)) });
+                        false
+                    } else {
+                        sr & 1 != 0
+                    }
+                }
+            }
+        )+
+    }
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+hal! {
+    TIM1: (0, 0, apb2enr, apb2rstr, pclk2, ppre2, [
+        C1: (ccmr1_input, ccr1),
+        C2: (ccmr1_input, ccr2),
+        C3: (ccmr2_input, ccr3),
+        C4: (ccmr2_input, ccr4),
+    ]),
+    TIM5: (3, 3, apb1enr, apb1rstr, pclk1, ppre1, [
+        C1: (ccmr1_input, ccr1),
+        C2: (ccmr1_input, ccr2),
+        C3: (ccmr2_input, ccr3),
+        C4: (ccmr2_input, ccr4),
+    ]),
+    TIM9: (16, 16, apb2enr, apb2rstr, pclk2, ppre2, [
+        C1: (ccmr1_input, ccr1),
+        C2: (ccmr1_input, ccr2),
+    ]),
+    TIM11: (18, 18, apb2enr, apb2rstr, pclk2, ppre2, [
+        C1: (ccmr1_input, ccr1),
+    ]),
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+slave!(TIM1, TIM5, TIM9,);
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+hal! {
+    TIM2: (0, 0, apb1enr, apb1rstr, pclk1, ppre1, [
+        C1: (ccmr1_input, ccr1),
+        C2: (ccmr1_input, ccr2),
+        C3: (ccmr2_input, ccr3),
+        C4: (ccmr2_input, ccr4),
+    ]),
+    TIM3: (1, 1, apb1enr, apb1rstr, pclk1, ppre1, [
+        C1: (ccmr1_input, ccr1),
+        C2: (ccmr1_input, ccr2),
+        C3: (ccmr2_input, ccr3),
+        C4: (ccmr2_input, ccr4),
+    ]),
+    TIM4: (2, 2, apb1enr, apb1rstr, pclk1, ppre1, [
+        C1: (ccmr1_input, ccr1),
+        C2: (ccmr1_input, ccr2),
+        C3: (ccmr2_input, ccr3),
+        C4: (ccmr2_input, ccr4),
+    ]),
+    TIM10: (17, 17, apb2enr, apb2rstr, pclk2, ppre2, [
+        C1: (ccmr1_input, ccr1),
+    ]),
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+slave!(TIM2, TIM3, TIM4,);
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+hal! {
+    TIM8: (1, 1, apb2enr, apb2rstr, pclk2, ppre2, [
+        C1: (ccmr1_input, ccr1),
+        C2: (ccmr1_input, ccr2),
+        C3: (ccmr2_input, ccr3),
+        C4: (ccmr2_input, ccr4),
+    ]),
+    TIM12: (6, 6, apb1enr, apb1rstr, pclk1, ppre1, [
+        C1: (ccmr1_input, ccr1),
+        C2: (ccmr1_input, ccr2),
+    ]),
+    TIM13: (7, 7, apb1enr, apb1rstr, pclk1, ppre1, [
+        C1: (ccmr1_input, ccr1),
+    ]),
+    TIM14: (8, 8, apb1enr, apb1rstr, pclk1, ppre1, [
+        C1: (ccmr1_input, ccr1),
+    ]),
+}
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+slave!(TIM8, TIM12,);
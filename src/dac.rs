@@ -1,18 +1,43 @@
 //! # API for the Digital to Analog converter
 //!
-//! Currently only supports writing to the DR of the DAC,
-//! just a basic one-shot conversion.
+//! Values are written with [`DacOut`], either 12 bit right aligned (`u16`) or 8 bit (`u8`).
+//! Both channels can be written at once through the `(C1, C2)` pair.
+//!
+//! By default a written value is converted immediately. With [`DacPin::enable_trigger`] the
+//! conversion waits for a timer, EXTI9 or a software trigger instead, which is also needed for
+//! the built-in noise and triangle wave generation. [`DacPin::dma_stream`] plays a table of
+//! samples, one sample per update event of a timer:
+//!
+//! ```ignore
+//! static SINE: [u16; 32] = [..];
+//!
+//! let dac = dp.DAC.constrain(gpioa.pa4.into_analog());
+//! let dma = StreamsTuple::new(dp.DMA1);
+//! let timer = Timer::tim6(dp.TIM6, 32.khz(), clocks);
+//!
+//! // 1 kHz sine wave
+//! let transfer = dac.dma_stream(timer, dma.5, &SINE);
+//! ```
 #![deny(unused_imports)]
 use core::mem;
 
+#[cfg(not(feature = "stm32f410"))]
+use crate::pac::{TIM2, TIM4, TIM7, TIM8};
 use crate::{
     bb,
+    dma::{
+        config::DmaConfig,
+        traits::{DMASet, PeriAddress, Stream},
+        Channel7, MemoryToPeripheral, Transfer,
+    },
     gpio::{
         gpioa::{PA4, PA5},
         Analog,
     },
-    pac::{DAC, RCC},
+    pac::{DAC, RCC, TIM5, TIM6},
+    timer::{MasterMode, Timer},
 };
+use embedded_dma::StaticReadBuffer;
 
 pub struct C1;
 pub struct C2;
@@ -22,8 +47,143 @@ pub trait DacOut<V> {
     fn get_value(&mut self) -> V;
 }
 
-pub trait DacPin {
+/// Event that starts a conversion
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    /// TIM6 TRGO
+    Tim6,
+    /// TIM8 TRGO
+    Tim8,
+    /// TIM7 TRGO
+    Tim7,
+    /// TIM5 TRGO
+    Tim5,
+    /// TIM2 TRGO
+    Tim2,
+    /// TIM4 TRGO
+    Tim4,
+    /// Rising edge on EXTI line 9
+    Exti9,
+    /// [`DacPin::trigger`]
+    Software,
+}
+
+impl Trigger {
+    fn bits(self) -> u8 {
+        match self {
+            Trigger::Tim6 => 0b000,
+            Trigger::Tim8 => 0b001,
+            Trigger::Tim7 => 0b010,
+            Trigger::Tim5 => 0b011,
+            Trigger::Tim2 => 0b100,
+            Trigger::Tim4 => 0b101,
+            Trigger::Exti9 => 0b110,
+            Trigger::Software => 0b111,
+        }
+    }
+}
+
+/// Waveform added to the written value on every trigger
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Wave {
+    /// Pseudo random noise, using the given number of bits (1 to 12) of the LFSR
+    Noise(u8),
+    /// Triangle counting up to `2^bits - 1` and back, `bits` from 1 to 12
+    Triangle(u8),
+}
+
+/// Timer that can trigger conversions with its update event
+pub trait TriggerTimer {
+    #[doc(hidden)]
+    const TRIGGER: Trigger;
+
+    /// Sets the update event as trigger output (TRGO)
+    #[doc(hidden)]
+    fn select_update_trgo(timer: &mut Timer<Self>)
+    where
+        Self: Sized;
+}
+
+macro_rules! trigger_timer {
+    ($($TIM:ident: $trigger:ident,)+) => {
+        $(
+            impl TriggerTimer for $TIM {
+                const TRIGGER: Trigger = Trigger::$trigger;
+
+                fn select_update_trgo(timer: &mut Timer<Self>) {
+                    timer.set_master_mode(MasterMode::Update);
+                }
+            }
+        )+
+    };
+}
+
+trigger_timer! {
+    TIM5: Tim5,
+    TIM6: Tim6,
+}
+
+#[cfg(not(feature = "stm32f410"))]
+trigger_timer! {
+    TIM2: Tim2,
+    TIM4: Tim4,
+    TIM7: Tim7,
+    TIM8: Tim8,
+}
+
+pub trait DacPin: Sized {
     fn enable(&mut self);
+
+    fn disable(&mut self);
+
+    /// Waits for `trigger` before converting a written value
+    fn enable_trigger(&mut self, trigger: Trigger);
+
+    /// Converts written values immediately
+    fn disable_trigger(&mut self);
+
+    /// Triggers a conversion if [`Trigger::Software`] is selected
+    fn trigger(&mut self);
+
+    /// Adds `wave` to the written value, the wave advances on every trigger
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of bits isn't between 1 and 12.
+    fn enable_wave(&mut self, wave: Wave);
+
+    fn disable_wave(&mut self);
+
+    /// Enables or disables the output buffer, which is enabled by default
+    ///
+    /// The buffer reduces the output impedance, without it the output can get closer to ground
+    /// and VREF+.
+    fn set_output_buffer(&mut self, enabled: bool);
+
+    /// Plays `buf` repeatedly, one 12 bit right aligned sample per update event of `timer`
+    ///
+    /// The sample rate is the frequency the timer has been started with.
+    fn dma_stream<TIM, STREAM, BUF>(
+        self,
+        timer: Timer<TIM>,
+        stream: STREAM,
+        buf: BUF,
+    ) -> DacTransfer<Self, TIM, STREAM, BUF>
+    where
+        Self: PeriAddress<MemSize = u16> + DMASet<STREAM, Channel7, MemoryToPeripheral>,
+        TIM: TriggerTimer,
+        STREAM: Stream,
+        BUF: StaticReadBuffer<Word = u16>;
+}
+
+/// Sample table played by [`DacPin::dma_stream`]
+pub struct DacTransfer<CH, TIM, STREAM, BUF>
+where
+    CH: PeriAddress,
+    STREAM: Stream,
+{
+    timer: Timer<TIM>,
+    transfer: Transfer<STREAM, Channel7, CH, MemoryToPeripheral, BUF>,
 }
 
 pub trait Pins<DAC> {
@@ -68,12 +228,138 @@ where
 }
 
 macro_rules! dac {
-    ($CX:ident, $en:ident, $cen:ident, $cal_flag:ident, $trim:ident, $mode:ident, $dhrx:ident, $dac_dor:ident, $daccxdhr:ident) => {
+    ($CX:ident, $en:ident, $boff:ident, $ten:ident, $tsel:ident, $wave:ident, $mamp:ident, $dmaen:ident, $swtrig:ident, $dmaudr:ident, $dhrx:ident, $dhr12l:ident, $dhr8r:ident, $dac_dor:ident) => {
         impl DacPin for $CX {
             fn enable(&mut self) {
                 let dac = unsafe { &(*DAC::ptr()) };
                 dac.cr.modify(|_, w| w.$en().set_bit());
             }
+
+            fn disable(&mut self) {
+                let dac = unsafe { &(*DAC::ptr()) };
+                dac.cr.modify(|_, w| w.$en().clear_bit());
+            }
+
+            fn enable_trigger(&mut self, trigger: Trigger) {
+                let dac = unsafe { &(*DAC::ptr()) };
+                // some chip variants declare `.bits()` as unsafe, some don't
+                #[allow(unused_unsafe)]
+                dac.cr
+                    .modify(|_, w| unsafe { w.$tsel().bits(trigger.bits()).$ten().set_bit() });
+            }
+
+            fn disable_trigger(&mut self) {
+                let dac = unsafe { &(*DAC::ptr()) };
+                dac.cr.modify(|_, w| w.$ten().clear_bit());
+            }
+
+            fn trigger(&mut self) {
+                let dac = unsafe { &(*DAC::ptr()) };
+                dac.swtrigr.write(|w| w.$swtrig().set_bit());
+            }
+
+            fn enable_wave(&mut self, wave: Wave) {
+                let (mode, bits) = match wave {
+                    Wave::Noise(bits) => (0b01, bits),
+                    Wave::Triangle(bits) => (0b10, bits),
+                };
+                assert!((1..=12).contains(&bits));
+
+                let dac = unsafe { &(*DAC::ptr()) };
+                dac.cr
+                    .modify(|_, w| unsafe { w.$wave().bits(mode).$mamp().bits(bits - 1) });
+            }
+
+            fn disable_wave(&mut self) {
+                let dac = unsafe { &(*DAC::ptr()) };
+                dac.cr.modify(|_, w| unsafe { w.$wave().bits(0b00) });
+            }
+
+            fn set_output_buffer(&mut self, enabled: bool) {
+                let dac = unsafe { &(*DAC::ptr()) };
+                dac.cr.modify(|_, w| w.$boff().bit(!enabled));
+            }
+
+            fn dma_stream<TIM, STREAM, BUF>(
+                self,
+                mut timer: Timer<TIM>,
+                stream: STREAM,
+                buf: BUF,
+            ) -> DacTransfer<Self, TIM, STREAM, BUF>
+            where
+                Self: PeriAddress<MemSize = u16> + DMASet<STREAM, Channel7, MemoryToPeripheral>,
+                TIM: TriggerTimer,
+                STREAM: Stream,
+                BUF: StaticReadBuffer<Word = u16>,
+            {
+                TIM::select_update_trgo(&mut timer);
+
+                let config = DmaConfig::default()
+                    .memory_increment(true)
+                    .circular_buffer(true);
+                let mut transfer =
+                    Transfer::init_memory_to_peripheral(stream, self, buf, None, config);
+
+                let dac = unsafe { &(*DAC::ptr()) };
+                transfer.start(|_| {
+                    #[allow(unused_unsafe)]
+                    dac.cr.modify(|_, w| unsafe {
+                        w.$tsel()
+                            .bits(TIM::TRIGGER.bits())
+                            .$ten()
+                            .set_bit()
+                            .$dmaen()
+                            .set_bit()
+                            .$en()
+                            .set_bit()
+                    })
+                });
+
+                DacTransfer { timer, transfer }
+            }
+        }
+
+        unsafe impl PeriAddress for $CX {
+            type MemSize = u16;
+
+            fn address(&self) -> u32 {
+                let dac = unsafe { &(*DAC::ptr()) };
+                &dac.$dhrx as *const _ as u32
+            }
+        }
+
+        impl<TIM, STREAM, BUF> DacTransfer<$CX, TIM, STREAM, BUF>
+        where
+            STREAM: Stream,
+            $CX: DMASet<STREAM, Channel7, MemoryToPeripheral>,
+            BUF: StaticReadBuffer<Word = u16>,
+        {
+            /// Returns true if the samples weren't written fast enough
+            pub fn is_underrun(&self) -> bool {
+                let dac = unsafe { &(*DAC::ptr()) };
+                dac.sr.read().$dmaudr().bit_is_set()
+            }
+
+            /// Stops the playback and returns the resources
+            ///
+            /// The output keeps the last sample.
+            pub fn stop(self) -> ($CX, Timer<TIM>, STREAM, BUF) {
+                let dac = unsafe { &(*DAC::ptr()) };
+                dac.cr
+                    .modify(|_, w| w.$dmaen().clear_bit().$ten().clear_bit());
+                dac.sr.write(|w| w.$dmaudr().set_bit());
+
+                let (stream, channel, buf, _) = self.transfer.free();
+                (channel, self.timer, stream, buf)
+            }
+        }
+
+        impl $CX {
+            /// Writes a 12 bit value aligned to the top of `val`
+            pub fn set_value_left_aligned(&mut self, val: u16) {
+                let dac = unsafe { &(*DAC::ptr()) };
+                dac.$dhr12l.write(|w| unsafe { w.bits(val as u32) });
+            }
         }
 
         impl DacOut<u16> for $CX {
@@ -87,9 +373,47 @@ macro_rules! dac {
                 dac.$dac_dor.read().bits() as u16
             }
         }
+
+        impl DacOut<u8> for $CX {
+            fn set_value(&mut self, val: u8) {
+                let dac = unsafe { &(*DAC::ptr()) };
+                dac.$dhr8r.write(|w| unsafe { w.bits(val as u32) });
+            }
+
+            fn get_value(&mut self) -> u8 {
+                let dac = unsafe { &(*DAC::ptr()) };
+                (dac.$dac_dor.read().bits() >> 4) as u8
+            }
+        }
     };
 }
 
+impl DacOut<(u16, u16)> for (C1, C2) {
+    /// Writes both channels at the same time
+    fn set_value(&mut self, (val1, val2): (u16, u16)) {
+        let dac = unsafe { &(*DAC::ptr()) };
+        dac.dhr12rd
+            .write(|w| w.dacc1dhr().bits(val1).dacc2dhr().bits(val2));
+    }
+
+    fn get_value(&mut self) -> (u16, u16) {
+        (self.0.get_value(), self.1.get_value())
+    }
+}
+
+impl DacOut<(u8, u8)> for (C1, C2) {
+    /// Writes both channels at the same time
+    fn set_value(&mut self, (val1, val2): (u8, u8)) {
+        let dac = unsafe { &(*DAC::ptr()) };
+        dac.dhr8rd
+            .write(|w| w.dacc1dhr().bits(val1).dacc2dhr().bits(val2));
+    }
+
+    fn get_value(&mut self) -> (u8, u8) {
+        (self.0.get_value(), self.1.get_value())
+    }
+}
+
 pub trait DacExt {
     fn constrain<PINS>(self, pins: PINS) -> PINS::Output
     where
@@ -105,5 +429,11 @@ impl DacExt for DAC {
     }
 }
 
-dac!(C1, en1, cen1, cal_flag1, otrim1, mode1, dhr12r1, dor1, dacc1dhr);
-dac!(C2, en2, cen2, cal_flag2, otrim2, mode2, dhr12r2, dor2, dacc2dhr);
+dac!(
+    C1, en1, boff1, ten1, tsel1, wave1, mamp1, dmaen1, swtrig1, dmaudr1, dhr12r1, dhr12l1, dhr8r1,
+    dor1
+);
+dac!(
+    C2, en2, boff2, ten2, tsel2, wave2, mamp2, dmaen2, swtrig2, dmaudr2, dhr12r2, dhr12l2, dhr8r2,
+    dor2
+);
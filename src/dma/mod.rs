@@ -4,8 +4,8 @@
 //! peripheral-stream-channel-direction, providing compile time checking.
 //!
 //! This module implements Memory To Memory, Peripheral To Memory and Memory to Peripheral
-//! transfers, double buffering is supported only for Peripheral To Memory and Memory to Peripheral
-//! transfers.
+//! transfers, double buffering and circular mode are supported only for Peripheral To Memory and
+//! Memory to Peripheral transfers.
 
 use core::{
     fmt::{self, Debug, Formatter},
@@ -547,6 +547,13 @@ macro_rules! dma_stream {
                     dma.st[Self::NUMBER].cr.modify(|_, w| w.dbm().bit(double_buffer));
                 }
 
+                #[inline(always)]
+                fn set_circular_buffer(&mut self, circular_buffer: bool) {
+                    //NOTE(unsafe) We only access the registers that belongs to the StreamX
+                    let dma = unsafe { &*I::ptr() };
+                    dma.st[Self::NUMBER].cr.modify(|_, w| w.circ().bit(circular_buffer));
+                }
+
                 #[inline(always)]
                 fn set_fifo_threshold(&mut self, fifo_threshold: config::FifoThreshold) {
                     //NOTE(unsafe) We only access the registers that belongs to the StreamX
@@ -733,6 +740,7 @@ pub mod config {
         pub(crate) direct_mode_error_interrupt: bool,
         pub(crate) fifo_error_interrupt: bool,
         pub(crate) double_buffer: bool,
+        pub(crate) circular_buffer: bool,
         pub(crate) fifo_threshold: FifoThreshold,
         pub(crate) fifo_enable: bool,
         pub(crate) memory_burst: BurstMode,
@@ -751,6 +759,7 @@ pub mod config {
                 direct_mode_error_interrupt: false,
                 fifo_error_interrupt: false,
                 double_buffer: false,
+                circular_buffer: false,
                 fifo_threshold: FifoThreshold::QuarterFull,
                 fifo_enable: false,
                 memory_burst: BurstMode::NoBurst,
@@ -815,6 +824,12 @@ pub mod config {
             self.double_buffer = double_buffer;
             self
         }
+        /// Set the circular_buffer.
+        #[inline(always)]
+        pub fn circular_buffer(mut self, circular_buffer: bool) -> Self {
+            self.circular_buffer = circular_buffer;
+            self
+        }
         /// Set the fifo_threshold.
         #[inline(always)]
         pub fn fifo_threshold(mut self, fifo_threshold: FifoThreshold) -> Self {
@@ -1141,8 +1156,8 @@ where
     ///
     /// # Panics
     ///
-    /// * When the FIFO is disabled or double buffering is enabled in `DmaConfig` while initializing
-    /// a memory to memory transfer.
+    /// * When the FIFO is disabled or double buffering or circular mode is enabled in `DmaConfig`
+    /// while initializing a memory to memory transfer.
     pub fn init_memory_to_memory(
         mut stream: STREAM,
         peripheral: PERIPHERAL,
@@ -1271,6 +1286,12 @@ where
         }
     }
 
+    /// Get the number of transfers (ndt) still remaining in the current buffer.
+    #[inline(always)]
+    pub fn number_of_transfers(&self) -> u16 {
+        STREAM::get_number_of_transfers()
+    }
+
     /// Clear all interrupts for the DMA stream.
     #[inline(always)]
     pub fn clear_interrupts(&mut self) {
@@ -1337,6 +1358,7 @@ where
         stream.set_direct_mode_error_interrupt_enable(config.direct_mode_error_interrupt);
         stream.set_fifo_error_interrupt_enable(config.fifo_error_interrupt);
         stream.set_double_buffer(config.double_buffer);
+        stream.set_circular_buffer(config.circular_buffer);
         stream.set_fifo_threshold(config.fifo_threshold);
         stream.set_fifo_enable(config.fifo_enable);
         stream.set_memory_burst(config.memory_burst);
@@ -1370,6 +1392,8 @@ where
                 panic!("Fifo disabled.");
             } else if config.double_buffer {
                 panic!("Double buffering enabled.");
+            } else if config.circular_buffer {
+                panic!("Circular buffering enabled.");
             }
         } else {
             // Set the peripheral address
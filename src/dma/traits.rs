@@ -151,6 +151,9 @@ pub trait Stream: Sealed {
     /// Enable/disable the double buffer (dbm) of the DMA stream.
     fn set_double_buffer(&mut self, double_buffer: bool);
 
+    /// Enable/disable the circular mode (circ) of the DMA stream.
+    fn set_circular_buffer(&mut self, circular_buffer: bool);
+
     /// Set the fifo threshold (fcr.fth) of the DMA stream.
     fn set_fifo_threshold(&mut self, fifo_threshold: config::FifoThreshold);
 
@@ -819,7 +822,6 @@ dma_map!(
     (Stream0<DMA1>, Channel4, pac::UART5, PeripheralToMemory), //UART5_RX
     (Stream2<DMA1>, Channel4, pac::UART4, PeripheralToMemory), //UART4_RX
     (Stream4<DMA1>, Channel4, pac::UART4, MemoryToPeripheral), //UART4_TX
-                                                               //(Stream6<DMA1>, Channel7, pac::DAC2, MemoryToPeripheral), //DAC2
 );
 
 #[cfg(any(
@@ -837,11 +839,7 @@ dma_map!(
     feature = "stm32f469",
     feature = "stm32f479",
 ))]
-address!(
-    (pac::UART4, dr, u8),
-    (pac::UART5, dr, u8),
-    //(pac::DAC, ??),
-);
+address!((pac::UART4, dr, u8), (pac::UART5, dr, u8),);
 
 #[cfg(any(
     feature = "stm32f417",
@@ -1003,7 +1001,6 @@ dma_map!(
 ))]
 address!((pac::HASH, din, u32), (pac::CRYP, din, u32),);
 
-/* Not sure how DAC works with DMA
 #[cfg(any(
     feature = "stm32f417",
     feature = "stm32f415",
@@ -1021,28 +1018,9 @@ address!((pac::HASH, din, u32), (pac::CRYP, din, u32),);
     feature = "stm32f479",
 ))]
 dma_map!(
-    (pac::DMA1, Stream5, Channel7, pac::DAC, MemoryToPeripheral), //DAC1
-);
-#[cfg(any(
-    feature = "stm32f417",
-    feature = "stm32f415",
-    feature = "stm32f405",
-    feature = "stm32f407",
-    feature = "stm32f410",
-    feature = "stm32f413",
-    feature = "stm32f423",
-    feature = "stm32f427",
-    feature = "stm32f439",
-    feature = "stm32f437",
-    feature = "stm32f429",
-    feature = "stm32f446",
-    feature = "stm32f469",
-    feature = "stm32f479",
-))]
-address!(
-    (pac::DAC, ??),
+    (Stream5<DMA1>, Channel7, crate::dac::C1, MemoryToPeripheral), //DAC1
+    (Stream6<DMA1>, Channel7, crate::dac::C2, MemoryToPeripheral), //DAC2
 );
-*/
 
 #[cfg(any(
     feature = "stm32f417",
@@ -0,0 +1,804 @@
+//! Flash memory
+//!
+//! The internal flash is read like normal memory through [`FlashExt::read`]. Erasing and
+//! programming it requires unlocking the control register first, which
+//! [`FlashExt::unlocked`] does until the returned [`UnlockedFlash`] is dropped.
+//!
+//! Flash is divided into sectors of 16, 64 and 128 KiB, which are the smallest unit that can be
+//! erased. [`FlashExt::sectors`] lists them for the running device.
+//!
+//! ```ignore
+//! let mut flash = dp.FLASH;
+//! let sector = flash.sectors().last().unwrap();
+//!
+//! let mut unlocked = flash.unlocked();
+//! unlocked.erase(sector.number)?;
+//! unlocked.program(sector.offset, &calibration)?;
+//! ```
+//!
+//! [`LockedFlash`] and [`UnlockedFlash`] also implement the `embedded-storage` NOR flash traits.
+//! As those traits require a uniform erase size, [`UnlockedFlash`] erases in 128 KiB units,
+//! [`FlashRegion`] gives access to a run of smaller sectors instead.
+
+use core::{ops::Range, ptr, slice};
+
+use embedded_storage::nor_flash::{MultiwriteNorFlash, NorFlash, ReadNorFlash};
+
+use crate::pac::FLASH;
+use crate::signature::FlashSize;
+
+/// Flash erase/program error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// Programming sequence error (PGSERR)
+    ProgrammingSequence,
+    /// Programming parallelism error (PGPERR), the access size doesn't match the parallelism
+    ProgrammingParallelism,
+    /// Programming alignment error (PGAERR), the data crosses a 128 bit row
+    ProgrammingAlignment,
+    /// Write protection error (WRPERR), the sector is write protected
+    WriteProtection,
+    /// Operation error (OPERR)
+    Operation,
+    /// The address range is outside the flash
+    OutOfBounds,
+    /// The address range doesn't start and end at a multiple of the erase size
+    NotAligned,
+    /// A sector of a [`FlashRegion`] doesn't have the region's sector size
+    SectorSizeMismatch,
+}
+
+impl Error {
+    fn read(flash: &FLASH) -> Option<Self> {
+        let sr = flash.sr.read();
+        if sr.pgserr().bit_is_set() {
+            Some(Error::ProgrammingSequence)
+        } else if sr.pgperr().bit_is_set() {
+            Some(Error::ProgrammingParallelism)
+        } else if sr.pgaerr().bit_is_set() {
+            Some(Error::ProgrammingAlignment)
+        } else if sr.wrperr().bit_is_set() {
+            Some(Error::WriteProtection)
+        } else if sr.operr().bit_is_set() {
+            Some(Error::Operation)
+        } else {
+            None
+        }
+    }
+}
+
+/// Widest access used to erase and program the flash
+///
+/// The flash can only be programmed with the full parallelism at a high enough supply voltage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Parallelism {
+    /// Byte access, 1.8 V to 3.6 V
+    X8,
+    /// Half-word access, 2.1 V to 3.6 V
+    X16,
+    /// Word access, 2.7 V to 3.6 V
+    X32,
+}
+
+impl Parallelism {
+    fn bytes(self) -> usize {
+        match self {
+            Parallelism::X8 => 1,
+            Parallelism::X16 => 2,
+            Parallelism::X32 => 4,
+        }
+    }
+
+    fn psize(self) -> u8 {
+        match self {
+            Parallelism::X8 => 0b00,
+            Parallelism::X16 => 0b01,
+            Parallelism::X32 => 0b10,
+        }
+    }
+}
+
+/// Flash sector
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlashSector {
+    /// Sector number, as passed to [`UnlockedFlash::erase`]
+    pub number: u8,
+    /// Offset from the start of the flash
+    pub offset: usize,
+    /// Size in bytes
+    pub size: usize,
+}
+
+impl FlashSector {
+    /// Returns true if `offset` is inside this sector
+    pub fn contains(&self, offset: usize) -> bool {
+        self.offset <= offset && offset < self.offset + self.size
+    }
+}
+
+/// Iterator over the sectors of the flash, returned by [`FlashExt::sectors`]
+///
+/// Every bank starts with four 16 KiB sectors, followed by one 64 KiB sector and 128 KiB sectors
+/// for the rest of the bank. The sectors of the second bank are numbered from 12.
+#[derive(Debug, Clone)]
+pub struct FlashSectors {
+    bank_size: usize,
+    banks: u8,
+    bank: u8,
+    index: u8,
+    bank_offset: usize,
+}
+
+impl FlashSectors {
+    /// Sectors of a flash of `flash_size` bytes, split into two equal banks if `dual_bank`
+    pub fn new(flash_size: usize, dual_bank: bool) -> Self {
+        let banks = if dual_bank { 2 } else { 1 };
+        FlashSectors {
+            bank_size: flash_size / usize::from(banks),
+            banks,
+            bank: 0,
+            index: 0,
+            bank_offset: 0,
+        }
+    }
+}
+
+impl Iterator for FlashSectors {
+    type Item = FlashSector;
+
+    fn next(&mut self) -> Option<FlashSector> {
+        if self.bank_offset >= self.bank_size {
+            self.bank += 1;
+            self.index = 0;
+            self.bank_offset = 0;
+        }
+        if self.bank >= self.banks {
+            return None;
+        }
+
+        let size = match self.index {
+            0..=3 => 0x4000,
+            4 => 0x1_0000,
+            _ => 0x2_0000,
+        };
+        let sector = FlashSector {
+            number: self.bank * 12 + self.index,
+            offset: usize::from(self.bank) * self.bank_size + self.bank_offset,
+            size,
+        };
+
+        self.index += 1;
+        self.bank_offset += size;
+        Some(sector)
+    }
+}
+
+/// Level of readout protection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadProtection {
+    /// No protection
+    Level0,
+    /// Flash can't be read by the debugger or when booting from RAM or system memory, going back
+    /// to level 0 erases the whole flash
+    Level1,
+    /// Like level 1, but the debug port and booting from RAM or system memory are disabled
+    /// **permanently**
+    Level2,
+}
+
+/// Brownout reset threshold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BorLevel {
+    /// Reset at the power-down threshold only
+    Off,
+    /// 2.1 V to 2.2 V
+    Level1,
+    /// 2.4 V to 2.5 V
+    Level2,
+    /// 2.7 V to 2.8 V
+    Level3,
+}
+
+/// Flash option bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OptionBytes {
+    pub read_protection: ReadProtection,
+    /// Write protected sectors, bit `n` protects sector `n`
+    pub write_protection: u32,
+    pub bor_level: BorLevel,
+}
+
+/// Extension trait for the flash memory
+#[allow(clippy::len_without_is_empty)]
+pub trait FlashExt {
+    /// Memory-mapped address of the flash
+    fn address(&self) -> usize;
+
+    /// Size of the flash in bytes
+    fn len(&self) -> usize;
+
+    /// Returns the flash contents
+    fn read(&self) -> &[u8] {
+        let ptr = self.address() as *const u8;
+        // NOTE(unsafe) the flash is memory mapped and only modified through `&mut self`
+        unsafe { slice::from_raw_parts(ptr, self.len()) }
+    }
+
+    /// Returns true if the flash is split into two banks
+    fn dual_bank(&self) -> bool;
+
+    /// Returns the sectors of the flash
+    fn sectors(&self) -> FlashSectors {
+        FlashSectors::new(self.len(), self.dual_bank())
+    }
+
+    /// Reads the option bytes
+    fn option_bytes(&self) -> OptionBytes;
+
+    /// Unlocks the flash for erasing and programming
+    fn unlocked(&mut self) -> UnlockedFlash<'_>;
+}
+
+impl FlashExt for FLASH {
+    fn address(&self) -> usize {
+        0x0800_0000
+    }
+
+    fn len(&self) -> usize {
+        FlashSize::get().bytes()
+    }
+
+    #[cfg(any(
+        feature = "stm32f427",
+        feature = "stm32f429",
+        feature = "stm32f437",
+        feature = "stm32f439",
+        feature = "stm32f469",
+        feature = "stm32f479",
+    ))]
+    fn dual_bank(&self) -> bool {
+        match self.len() / 1024 {
+            // DB1M option bit
+            1024 => self.optcr.read().bits() & (1 << 30) != 0,
+            2048 => true,
+            _ => false,
+        }
+    }
+
+    #[cfg(not(any(
+        feature = "stm32f427",
+        feature = "stm32f429",
+        feature = "stm32f437",
+        feature = "stm32f439",
+        feature = "stm32f469",
+        feature = "stm32f479",
+    )))]
+    fn dual_bank(&self) -> bool {
+        false
+    }
+
+    fn option_bytes(&self) -> OptionBytes {
+        let optcr = self.optcr.read();
+
+        let read_protection = match optcr.rdp().bits() {
+            0xAA => ReadProtection::Level0,
+            0xCC => ReadProtection::Level2,
+            _ => ReadProtection::Level1,
+        };
+        let bor_level = match optcr.bor_lev().bits() {
+            0b11 => BorLevel::Off,
+            0b10 => BorLevel::Level1,
+            0b01 => BorLevel::Level2,
+            _ => BorLevel::Level3,
+        };
+
+        // nWRP bits are cleared for protected sectors
+        let write_protection = !(optcr.bits() >> NWRP_SHIFT) & NWRP_MASK;
+        // The second bank has its own nWRP bits
+        #[cfg(any(
+            feature = "stm32f427",
+            feature = "stm32f429",
+            feature = "stm32f437",
+            feature = "stm32f439",
+            feature = "stm32f469",
+            feature = "stm32f479",
+        ))]
+        let write_protection =
+            write_protection | (!(self.optcr1.read().bits() >> NWRP_SHIFT) & NWRP_MASK) << 12;
+
+        OptionBytes {
+            read_protection,
+            write_protection,
+            bor_level,
+        }
+    }
+
+    fn unlocked(&mut self) -> UnlockedFlash<'_> {
+        unlock(self);
+        UnlockedFlash {
+            flash: self,
+            parallelism: Parallelism::X8,
+        }
+    }
+}
+
+const UNLOCK_KEY1: u32 = 0x4567_0123;
+const UNLOCK_KEY2: u32 = 0xCDEF_89AB;
+const OPT_UNLOCK_KEY1: u32 = 0x0819_2A3B;
+const OPT_UNLOCK_KEY2: u32 = 0x4C5D_6E7F;
+
+const NWRP_SHIFT: u32 = 16;
+#[cfg(any(feature = "stm32f413", feature = "stm32f423"))]
+const NWRP_MASK: u32 = 0xFFFF;
+#[cfg(not(any(feature = "stm32f413", feature = "stm32f423")))]
+const NWRP_MASK: u32 = 0xFFF;
+
+fn unlock(flash: &FLASH) {
+    flash.keyr.write(|w| unsafe { w.bits(UNLOCK_KEY1) });
+    flash.keyr.write(|w| unsafe { w.bits(UNLOCK_KEY2) });
+    assert!(flash.cr.read().lock().bit_is_clear())
+}
+
+fn lock(flash: &FLASH) {
+    flash.cr.modify(|_, w| w.lock().set_bit());
+}
+
+/// Result of [`FlashExt::unlocked`], locks the flash again when dropped
+///
+/// # Examples
+///
+/// ```ignore
+/// let mut unlocked = flash.unlocked();
+/// unlocked.set_parallelism(Parallelism::X32);
+/// unlocked.erase(7)?;
+/// unlocked.program(0x6_0000, &data)?;
+/// ```
+pub struct UnlockedFlash<'a> {
+    flash: &'a mut FLASH,
+    parallelism: Parallelism,
+}
+
+impl Drop for UnlockedFlash<'_> {
+    fn drop(&mut self) {
+        lock(self.flash);
+    }
+}
+
+impl UnlockedFlash<'_> {
+    /// Sets the widest access allowed by the supply voltage, byte access is used by default
+    pub fn set_parallelism(&mut self, parallelism: Parallelism) {
+        self.parallelism = parallelism;
+    }
+
+    /// Erases a sector, see [`FlashExt::sectors`] for the numbers
+    ///
+    /// Returns [`Error::OutOfBounds`] if the flash has no sector `sector`.
+    pub fn erase(&mut self, sector: u8) -> Result<(), Error> {
+        if !self.flash.sectors().any(|s| s.number == sector) {
+            return Err(Error::OutOfBounds);
+        }
+
+        // The second bank starts at 16 on dual bank devices
+        #[cfg(any(
+            feature = "stm32f427",
+            feature = "stm32f429",
+            feature = "stm32f437",
+            feature = "stm32f439",
+            feature = "stm32f469",
+            feature = "stm32f479",
+        ))]
+        let snb = if sector < 12 { sector } else { sector + 4 };
+        #[cfg(not(any(
+            feature = "stm32f427",
+            feature = "stm32f429",
+            feature = "stm32f437",
+            feature = "stm32f439",
+            feature = "stm32f469",
+            feature = "stm32f479",
+        )))]
+        let snb = sector;
+
+        // No other operation may be ongoing
+        self.wait_ready();
+        self.clear_errors();
+        let psize = self.parallelism.psize();
+        self.flash
+            .cr
+            .modify(|_, w| unsafe { w.ser().set_bit().snb().bits(snb).psize().bits(psize) });
+        // The erase is started by a separate write, RM0090 3.6.3
+        self.flash.cr.modify(|_, w| w.strt().set_bit());
+        self.wait_ready();
+        self.flash.cr.modify(|_, w| w.ser().clear_bit());
+
+        self.ok()
+    }
+
+    /// Programs `bytes` at `offset` from the start of the flash
+    ///
+    /// The data is written in the widest accesses allowed by the parallelism and alignment. The
+    /// target has to be erased.
+    pub fn program(&mut self, mut offset: usize, mut bytes: &[u8]) -> Result<(), Error> {
+        while !bytes.is_empty() {
+            let mut width = self.parallelism.bytes();
+            while offset & (width - 1) != 0 || bytes.len() < width {
+                width /= 2;
+            }
+
+            match width {
+                4 => {
+                    let word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                    self.program_word(offset, word)?;
+                }
+                2 => self.program_half_word(offset, u16::from_le_bytes([bytes[0], bytes[1]]))?,
+                _ => self.program_byte(offset, bytes[0])?,
+            }
+
+            offset += width;
+            bytes = &bytes[width..];
+        }
+
+        Ok(())
+    }
+
+    /// Programs a byte at `offset` from the start of the flash
+    pub fn program_byte(&mut self, offset: usize, byte: u8) -> Result<(), Error> {
+        self.program_with(Parallelism::X8, offset, |ptr| unsafe {
+            ptr::write_volatile(ptr, byte)
+        })
+    }
+
+    /// Programs a half-word at `offset` from the start of the flash
+    ///
+    /// Returns [`Error::ProgrammingParallelism`] if the parallelism is [`Parallelism::X8`] and
+    /// [`Error::ProgrammingAlignment`] if `offset` isn't aligned.
+    pub fn program_half_word(&mut self, offset: usize, half_word: u16) -> Result<(), Error> {
+        self.program_with(Parallelism::X16, offset, |ptr| unsafe {
+            ptr::write_volatile(ptr as *mut u16, half_word)
+        })
+    }
+
+    /// Programs a word at `offset` from the start of the flash
+    ///
+    /// Returns [`Error::ProgrammingParallelism`] unless the parallelism is [`Parallelism::X32`]
+    /// and [`Error::ProgrammingAlignment`] if `offset` isn't aligned.
+    pub fn program_word(&mut self, offset: usize, word: u32) -> Result<(), Error> {
+        self.program_with(Parallelism::X32, offset, |ptr| unsafe {
+            ptr::write_volatile(ptr as *mut u32, word)
+        })
+    }
+
+    /// Writes the option bytes, they take effect after the next reset
+    ///
+    /// Lowering the read protection from level 1 to level 0 erases the whole flash, level 2 can't
+    /// be left again.
+    pub fn write_option_bytes(&mut self, option_bytes: &OptionBytes) -> Result<(), Error> {
+        let rdp = match option_bytes.read_protection {
+            ReadProtection::Level0 => 0xAA,
+            ReadProtection::Level1 => 0x55,
+            ReadProtection::Level2 => 0xCC,
+        };
+        let bor_lev = match option_bytes.bor_level {
+            BorLevel::Off => 0b11,
+            BorLevel::Level1 => 0b10,
+            BorLevel::Level2 => 0b01,
+            BorLevel::Level3 => 0b00,
+        };
+        let nwrp = !option_bytes.write_protection & NWRP_MASK;
+
+        self.flash
+            .optkeyr
+            .write(|w| unsafe { w.bits(OPT_UNLOCK_KEY1) });
+        self.flash
+            .optkeyr
+            .write(|w| unsafe { w.bits(OPT_UNLOCK_KEY2) });
+
+        self.clear_errors();
+        self.wait_ready();
+
+        #[cfg(any(
+            feature = "stm32f427",
+            feature = "stm32f429",
+            feature = "stm32f437",
+            feature = "stm32f439",
+            feature = "stm32f469",
+            feature = "stm32f479",
+        ))]
+        {
+            let nwrp1 = !(option_bytes.write_protection >> 12) & NWRP_MASK;
+            self.flash.optcr1.modify(|r, w| unsafe {
+                w.bits(r.bits() & !(NWRP_MASK << NWRP_SHIFT) | nwrp1 << NWRP_SHIFT)
+            });
+        }
+        self.flash.optcr.modify(|r, w| unsafe {
+            w.bits(r.bits() & !(NWRP_MASK << NWRP_SHIFT) | nwrp << NWRP_SHIFT)
+                .rdp()
+                .bits(rdp)
+                .bor_lev()
+                .bits(bor_lev)
+        });
+        self.flash.optcr.modify(|_, w| w.optstrt().set_bit());
+        self.wait_ready();
+
+        self.flash.optcr.modify(|_, w| w.optlock().set_bit());
+
+        self.ok()
+    }
+
+    fn program_with(
+        &mut self,
+        access: Parallelism,
+        offset: usize,
+        write: impl FnOnce(*mut u8),
+    ) -> Result<(), Error> {
+        if access > self.parallelism {
+            return Err(Error::ProgrammingParallelism);
+        }
+        if offset & (access.bytes() - 1) != 0 {
+            return Err(Error::ProgrammingAlignment);
+        }
+
+        self.clear_errors();
+        let psize = access.psize();
+        // some chip variants declare `.bits()` as unsafe, some don't
+        #[allow(unused_unsafe)]
+        self.flash
+            .cr
+            .modify(|_, w| unsafe { w.psize().bits(psize).pg().set_bit() });
+
+        write((self.flash.address() + offset) as *mut u8);
+        self.wait_ready();
+
+        self.flash.cr.modify(|_, w| w.pg().clear_bit());
+
+        self.ok()
+    }
+
+    fn ok(&self) -> Result<(), Error> {
+        Error::read(self.flash).map(Err).unwrap_or(Ok(()))
+    }
+
+    fn clear_errors(&mut self) {
+        self.flash.sr.write(|w| {
+            w.pgserr()
+                .set_bit()
+                .pgperr()
+                .set_bit()
+                .pgaerr()
+                .set_bit()
+                .wrperr()
+                .set_bit()
+                .operr()
+                .set_bit()
+                .eop()
+                .set_bit()
+        });
+    }
+
+    fn wait_ready(&self) {
+        while self.flash.sr.read().bsy().bit_is_set() {}
+    }
+}
+
+/// Read-only flash for `embedded-storage`
+pub struct LockedFlash {
+    flash: FLASH,
+}
+
+impl LockedFlash {
+    pub fn new(flash: FLASH) -> Self {
+        LockedFlash { flash }
+    }
+
+    /// Releases the FLASH peripheral
+    pub fn release(self) -> FLASH {
+        self.flash
+    }
+}
+
+impl FlashExt for LockedFlash {
+    fn address(&self) -> usize {
+        self.flash.address()
+    }
+
+    fn len(&self) -> usize {
+        self.flash.len()
+    }
+
+    fn dual_bank(&self) -> bool {
+        self.flash.dual_bank()
+    }
+
+    fn option_bytes(&self) -> OptionBytes {
+        self.flash.option_bytes()
+    }
+
+    fn unlocked(&mut self) -> UnlockedFlash<'_> {
+        self.flash.unlocked()
+    }
+}
+
+fn read_nor(flash: &impl FlashExt, offset: u32, bytes: &mut [u8]) -> Result<(), Error> {
+    let offset = offset as usize;
+    let data = flash
+        .read()
+        .get(offset..offset + bytes.len())
+        .ok_or(Error::OutOfBounds)?;
+    bytes.copy_from_slice(data);
+    Ok(())
+}
+
+impl ReadNorFlash for LockedFlash {
+    type Error = Error;
+
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Error> {
+        read_nor(self, offset, bytes)
+    }
+
+    fn capacity(&self) -> usize {
+        self.len()
+    }
+}
+
+impl ReadNorFlash for UnlockedFlash<'_> {
+    type Error = Error;
+
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Error> {
+        read_nor(self.flash, offset, bytes)
+    }
+
+    fn capacity(&self) -> usize {
+        self.flash.len()
+    }
+}
+
+/// Erases in units of the largest sector size
+///
+/// **The 16 KiB and 64 KiB sectors at the start of a bank can only be erased together**, as a
+/// single 128 KiB unit. Use a [`FlashRegion`] to erase them individually, e.g. to keep data in
+/// sectors that share that unit with code.
+impl NorFlash for UnlockedFlash<'_> {
+    const WRITE_SIZE: usize = 1;
+
+    const ERASE_SIZE: usize = 0x2_0000;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Error> {
+        let (from, to) = (from as usize, to as usize);
+        if from > to || to > self.flash.len() {
+            return Err(Error::OutOfBounds);
+        }
+        if from % Self::ERASE_SIZE != 0 || to % Self::ERASE_SIZE != 0 {
+            return Err(Error::NotAligned);
+        }
+
+        for sector in self.flash.sectors() {
+            if from <= sector.offset && sector.offset < to {
+                UnlockedFlash::erase(self, sector.number)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Error> {
+        let offset = offset as usize;
+        if offset + bytes.len() > self.flash.len() {
+            return Err(Error::OutOfBounds);
+        }
+        self.program(offset, bytes)
+    }
+}
+
+/// Programming can only clear bits, so writing the same bytes again is allowed
+impl MultiwriteNorFlash for UnlockedFlash<'_> {}
+
+/// A run of consecutive sectors of `SECTOR_SIZE` bytes, used as `embedded-storage` flash
+///
+/// Offsets are relative to the start of the region and the sector size is the erase size, so
+/// the small sectors at the start of a bank can be erased individually.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Sectors 2 and 3 are 16 KiB each
+/// let mut region = FlashRegion::<0x4000>::new(flash.unlocked(), 2..4)?;
+/// region.erase(0, 0x4000)?;
+/// region.write(0, &data)?;
+/// ```
+pub struct FlashRegion<'a, const SECTOR_SIZE: usize> {
+    flash: UnlockedFlash<'a>,
+    first_sector: u8,
+    offset: usize,
+    len: usize,
+}
+
+impl<'a, const SECTOR_SIZE: usize> FlashRegion<'a, SECTOR_SIZE> {
+    /// Creates a region over `sectors`, see [`FlashExt::sectors`] for the numbers
+    ///
+    /// Returns [`Error::OutOfBounds`] if `sectors` is empty or not all of them exist, and
+    /// [`Error::SectorSizeMismatch`] if one of them isn't `SECTOR_SIZE` bytes large.
+    pub fn new(flash: UnlockedFlash<'a>, sectors: Range<u8>) -> Result<Self, Error> {
+        let mut offset = None;
+        let mut count = 0;
+        for sector in flash.flash.sectors() {
+            if sectors.contains(&sector.number) {
+                if sector.size != SECTOR_SIZE {
+                    return Err(Error::SectorSizeMismatch);
+                }
+                offset.get_or_insert(sector.offset);
+                count += 1;
+            }
+        }
+
+        match offset {
+            Some(offset) if count == sectors.len() => Ok(FlashRegion {
+                flash,
+                first_sector: sectors.start,
+                offset,
+                len: count * SECTOR_SIZE,
+            }),
+            _ => Err(Error::OutOfBounds),
+        }
+    }
+
+    /// Offset of the region from the start of the flash
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Releases the unlocked flash
+    pub fn release(self) -> UnlockedFlash<'a> {
+        self.flash
+    }
+}
+
+impl<const SECTOR_SIZE: usize> ReadNorFlash for FlashRegion<'_, SECTOR_SIZE> {
+    type Error = Error;
+
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Error> {
+        if offset as usize + bytes.len() > self.len {
+            return Err(Error::OutOfBounds);
+        }
+        read_nor(self.flash.flash, self.offset as u32 + offset, bytes)
+    }
+
+    fn capacity(&self) -> usize {
+        self.len
+    }
+}
+
+impl<const SECTOR_SIZE: usize> NorFlash for FlashRegion<'_, SECTOR_SIZE> {
+    const WRITE_SIZE: usize = 1;
+
+    const ERASE_SIZE: usize = SECTOR_SIZE;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Error> {
+        let (from, to) = (from as usize, to as usize);
+        if from > to || to > self.len {
+            return Err(Error::OutOfBounds);
+        }
+        if from % SECTOR_SIZE != 0 || to % SECTOR_SIZE != 0 {
+            return Err(Error::NotAligned);
+        }
+
+        for index in from / SECTOR_SIZE..to / SECTOR_SIZE {
+            self.flash.erase(self.first_sector + index as u8)?;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Error> {
+        let offset = offset as usize;
+        if offset + bytes.len() > self.len {
+            return Err(Error::OutOfBounds);
+        }
+        self.flash.program(self.offset + offset, bytes)
+    }
+}
+
+/// Programming can only clear bits, so writing the same bytes again is allowed
+impl<const SECTOR_SIZE: usize> MultiwriteNorFlash for FlashRegion<'_, SECTOR_SIZE> {}
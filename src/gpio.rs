@@ -1,7 +1,10 @@
 //! General Purpose Input / Output
 
+use core::convert::Infallible;
 use core::marker::PhantomData;
 
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
 use crate::pac::EXTI;
 use crate::syscfg::SysCfg;
 
@@ -94,6 +97,19 @@ pub trait ExtiPin {
     fn check_interrupt(&self) -> bool;
 }
 
+/// Open drain alternate function pin that can temporarily be used as an open drain output
+///
+/// This is used to bit-bang a bus while the peripheral driving it is stuck, e.g. to recover an
+/// I2C bus.
+pub trait AsOpenDrainOutput {
+    /// The pin in open drain output mode
+    type Output: OutputPin<Error = Infallible> + InputPin<Error = Infallible>;
+
+    /// Switches the pin to open drain output mode, calls `f` with it and switches the pin back to
+    /// its alternate function
+    fn with_open_drain_output<R>(&mut self, f: impl FnOnce(&mut Self::Output) -> R) -> R;
+}
+
 macro_rules! exti_erased {
     ($PIN:ty, $extigpionr:expr) => {
         impl<MODE> ExtiPin for $PIN {
@@ -252,7 +268,7 @@ macro_rules! gpio {
             use super::{
                 Alternate, AlternateOD, Floating, GpioExt, Input, OpenDrain, Output, Speed,
                 PullDown, PullUp, PushPull, AF0, AF1, AF2, AF3, AF4, AF5, AF6, AF7, AF8, AF9, AF10,
-                AF11, AF12, AF13, AF14, AF15, Analog, Edge, ExtiPin,
+                AF11, AF12, AF13, AF14, AF15, Analog, AsOpenDrainOutput, Edge, ExtiPin,
             };
 
             /// GPIO parts
@@ -758,6 +774,35 @@ macro_rules! gpio {
                     }
                 }
 
+                impl<MODE> AsOpenDrainOutput for $PXi<AlternateOD<MODE>> {
+                    type Output = $PXi<Output<OpenDrain>>;
+
+                    fn with_open_drain_output<R>(
+                        &mut self,
+                        f: impl FnOnce(&mut Self::Output) -> R,
+                    ) -> R {
+                        let offset = 2 * $i;
+                        unsafe {
+                            // Release the pin before it is switched to output mode
+                            (*$GPIOX::ptr()).bsrr.write(|w| w.bits(1 << $i));
+                            &(*$GPIOX::ptr()).moder.modify(|r, w| {
+                                w.bits((r.bits() & !(0b11 << offset)) | (0b01 << offset))
+                            })
+                        };
+
+                        let result = f(&mut $PXi { _mode: PhantomData });
+
+                        // The alternate function selection and output type are left untouched
+                        unsafe {
+                            &(*$GPIOX::ptr()).moder.modify(|r, w| {
+                                w.bits((r.bits() & !(0b11 << offset)) | (0b10 << offset))
+                            })
+                        };
+
+                        result
+                    }
+                }
+
                 impl<MODE> $PXi<MODE> {
                     /// Erases the pin number from the type
                     ///
@@ -0,0 +1,529 @@
+//! Interrupt driven I2C master
+//!
+//! [`InterruptI2c`] performs the same transfers as the blocking [`I2c`](super::I2c) driver, but
+//! advances them from the I2C event and error interrupt handlers instead of busy waiting on the
+//! status flags. A transfer is started with `start_write`, `start_read` or `start_write_read`,
+//! the interrupt handlers call `handle_event` and `handle_error`, and `poll` reports whether the
+//! transfer has finished.
+//!
+//! Long reads can be performed by a DMA stream with `start_read_dma` and
+//! `start_write_read_dma`, the DMA transfer complete interrupt handler then has to call
+//! `handle_dma_complete`.
+//!
+//! ```ignore
+//! #[task(binds = I2C1_EV, resources = [i2c])]
+//! fn i2c1_ev(cx: i2c1_ev::Context) {
+//!     cx.resources.i2c.handle_event();
+//! }
+//!
+//! #[task(binds = I2C1_ER, resources = [i2c])]
+//! fn i2c1_er(cx: i2c1_er::Context) {
+//!     cx.resources.i2c.handle_error();
+//! }
+//! ```
+
+use core::marker::PhantomData;
+use core::mem;
+
+use super::{check_and_clear_error_flags, Error, I2c, Instance};
+use crate::dma::{
+    traits::{Channel, DMASet, PeriAddress, Stream},
+    PeripheralToMemory, Transfer,
+};
+use crate::rcc::Clocks;
+use embedded_dma::StaticWriteBuffer;
+
+/// Error of an interrupt driven transfer
+#[derive(Debug)]
+pub struct TransferError {
+    /// The error that aborted the transfer
+    pub error: Error,
+    /// The receive buffer of the transfer, if there was one
+    pub buffer: Option<&'static mut [u8]>,
+}
+
+/// Where the received bytes go
+enum ReadBuf {
+    /// Bytes are read by the event interrupt handler
+    Interrupt(&'static mut [u8]),
+    /// Bytes are read by a DMA stream, contains the number of bytes
+    Dma(usize),
+}
+
+impl ReadBuf {
+    fn len(&self) -> usize {
+        match self {
+            ReadBuf::Interrupt(buf) => buf.len(),
+            ReadBuf::Dma(len) => *len,
+        }
+    }
+
+    fn into_buffer(self) -> Option<&'static mut [u8]> {
+        match self {
+            ReadBuf::Interrupt(buf) => Some(buf),
+            ReadBuf::Dma(_) => None,
+        }
+    }
+}
+
+enum State {
+    Idle,
+    Write {
+        addr: u8,
+        bytes: &'static [u8],
+        pos: usize,
+        read: Option<ReadBuf>,
+    },
+    Read {
+        addr: u8,
+        buf: ReadBuf,
+        pos: usize,
+    },
+    Done(Option<&'static mut [u8]>),
+    Failed(TransferError),
+}
+
+/// DMA handle to receive I2C data with a [`Transfer`]
+pub struct Rx<I2C> {
+    _i2c: PhantomData<I2C>,
+}
+
+unsafe impl<I2C> PeriAddress for Rx<I2C>
+where
+    I2C: Instance,
+{
+    #[inline(always)]
+    fn address(&self) -> u32 {
+        // NOTE(unsafe) Only the address of the data register is used
+        &unsafe { &*I2C::ptr() }.dr as *const _ as u32
+    }
+
+    type MemSize = u8;
+}
+
+// The receive handle can use the same streams and channels as the peripheral itself
+unsafe impl<I2C, STREAM, CHANNEL> DMASet<STREAM, CHANNEL, PeripheralToMemory> for Rx<I2C> where
+    I2C: DMASet<STREAM, CHANNEL, PeripheralToMemory>
+{
+}
+
+/// Interrupt driven I2C master
+pub struct InterruptI2c<I2C: Instance, PINS> {
+    i2c: I2C,
+    pins: PINS,
+    clocks: Clocks,
+    timeout: Option<u64>,
+    state: State,
+}
+
+impl<I2C, PINS> InterruptI2c<I2C, PINS>
+where
+    I2C: Instance,
+{
+    pub(super) fn new(i2c: I2c<I2C, PINS>) -> Self {
+        InterruptI2c {
+            i2c: i2c.i2c,
+            pins: i2c.pins,
+            clocks: i2c.clocks,
+            timeout: i2c.timeout,
+            state: State::Idle,
+        }
+    }
+
+    /// Converts back into the blocking driver
+    ///
+    /// # Panics
+    ///
+    /// * When a transfer is in progress.
+    pub fn into_blocking(self) -> I2c<I2C, PINS> {
+        assert!(!self.is_busy(), "Transfer in progress");
+        I2c {
+            i2c: self.i2c,
+            pins: self.pins,
+            clocks: self.clocks,
+            timeout: self.timeout,
+            elapsed: 0,
+            last_poll: 0,
+        }
+    }
+
+    /// Returns true while a transfer is in progress
+    pub fn is_busy(&self) -> bool {
+        matches!(self.state, State::Write { .. } | State::Read { .. })
+    }
+
+    /// Starts writing `bytes` to the device at address `addr`
+    ///
+    /// # Panics
+    ///
+    /// * When a transfer is already in progress.
+    pub fn start_write(&mut self, addr: u8, bytes: &'static [u8]) {
+        self.start(State::Write {
+            addr,
+            bytes,
+            pos: 0,
+            read: None,
+        })
+    }
+
+    /// Starts reading `buffer.len()` bytes from the device at address `addr`
+    ///
+    /// # Panics
+    ///
+    /// * When a transfer is already in progress.
+    /// * When `buffer` is empty.
+    pub fn start_read(&mut self, addr: u8, buffer: &'static mut [u8]) {
+        self.start(State::Read {
+            addr,
+            buf: ReadBuf::Interrupt(buffer),
+            pos: 0,
+        })
+    }
+
+    /// Starts writing `bytes` to the device at address `addr`, followed by a repeated start and a
+    /// read of `buffer.len()` bytes
+    ///
+    /// # Panics
+    ///
+    /// * When a transfer is already in progress.
+    /// * When `buffer` is empty.
+    pub fn start_write_read(&mut self, addr: u8, bytes: &'static [u8], buffer: &'static mut [u8]) {
+        self.start(State::Write {
+            addr,
+            bytes,
+            pos: 0,
+            read: Some(ReadBuf::Interrupt(buffer)),
+        })
+    }
+
+    /// Returns a handle that can be used as the peripheral of a DMA [`Transfer`] for
+    /// `start_read_dma` and `start_write_read_dma`
+    pub fn dma_rx(&self) -> Rx<I2C> {
+        Rx { _i2c: PhantomData }
+    }
+
+    /// Starts reading from the device at address `addr` into the buffer of `transfer`
+    ///
+    /// The DMA stream is started by this method, the number of bytes read is the number of
+    /// transfers remaining in `transfer` and must be at least 2. Call `handle_dma_complete` from
+    /// the DMA transfer complete interrupt handler.
+    ///
+    /// # Panics
+    ///
+    /// * When a transfer is already in progress.
+    /// * When `transfer` has less than 2 transfers remaining.
+    pub fn start_read_dma<STREAM, CHANNEL, BUF>(
+        &mut self,
+        addr: u8,
+        transfer: &mut Transfer<STREAM, CHANNEL, Rx<I2C>, PeripheralToMemory, BUF>,
+    ) where
+        STREAM: Stream,
+        CHANNEL: Channel,
+        Rx<I2C>: DMASet<STREAM, CHANNEL, PeripheralToMemory>,
+        BUF: StaticWriteBuffer<Word = u8>,
+    {
+        assert!(!self.is_busy(), "Transfer in progress");
+        let len = usize::from(transfer.number_of_transfers());
+        self.start(State::Read {
+            addr,
+            buf: ReadBuf::Dma(len),
+            pos: 0,
+        });
+        transfer.start(|_| {});
+    }
+
+    /// Starts writing `bytes` to the device at address `addr`, followed by a repeated start and a
+    /// read into the buffer of `transfer`
+    ///
+    /// See `start_read_dma` for the requirements of `transfer`.
+    ///
+    /// # Panics
+    ///
+    /// * When a transfer is already in progress.
+    /// * When `transfer` has less than 2 transfers remaining.
+    pub fn start_write_read_dma<STREAM, CHANNEL, BUF>(
+        &mut self,
+        addr: u8,
+        bytes: &'static [u8],
+        transfer: &mut Transfer<STREAM, CHANNEL, Rx<I2C>, PeripheralToMemory, BUF>,
+    ) where
+        STREAM: Stream,
+        CHANNEL: Channel,
+        Rx<I2C>: DMASet<STREAM, CHANNEL, PeripheralToMemory>,
+        BUF: StaticWriteBuffer<Word = u8>,
+    {
+        assert!(!self.is_busy(), "Transfer in progress");
+        let len = usize::from(transfer.number_of_transfers());
+        self.start(State::Write {
+            addr,
+            bytes,
+            pos: 0,
+            read: Some(ReadBuf::Dma(len)),
+        });
+        transfer.start(|_| {});
+    }
+
+    /// Returns the result of the last transfer once it has finished
+    ///
+    /// On success, the receive buffer is returned if the transfer had one. Returns `WouldBlock`
+    /// while the transfer is in progress.
+    ///
+    /// # Panics
+    ///
+    /// * When no transfer has been started since the last result was returned.
+    pub fn poll(&mut self) -> nb::Result<Option<&'static mut [u8]>, TransferError> {
+        match mem::replace(&mut self.state, State::Idle) {
+            State::Done(buffer) => Ok(buffer),
+            State::Failed(error) => Err(nb::Error::Other(error)),
+            State::Idle => panic!("No transfer started"),
+            state => {
+                self.state = state;
+                Err(nb::Error::WouldBlock)
+            }
+        }
+    }
+
+    /// Advances the current transfer, must be called from the I2C event interrupt handler
+    pub fn handle_event(&mut self) {
+        let sr1 = self.i2c.sr1.read();
+
+        self.state = match mem::replace(&mut self.state, State::Idle) {
+            State::Write {
+                addr,
+                bytes,
+                mut pos,
+                read,
+            } => {
+                if sr1.sb().bit_is_set() {
+                    // Send the address in write mode
+                    self.i2c
+                        .dr
+                        .write(|w| unsafe { w.bits(u32::from(addr) << 1) });
+                } else if sr1.addr().bit_is_set() {
+                    // Clear condition by reading SR2
+                    self.i2c.sr2.read();
+                    if bytes.is_empty() {
+                        return self.finish_write(addr, read);
+                    }
+                } else if sr1.btf().bit_is_set() && pos == bytes.len() {
+                    return self.finish_write(addr, read);
+                } else if sr1.tx_e().bit_is_set() && pos < bytes.len() {
+                    self.i2c
+                        .dr
+                        .write(|w| unsafe { w.bits(u32::from(bytes[pos])) });
+                    pos += 1;
+                    if pos == bytes.len() {
+                        // Wait for the last byte to be transferred
+                        self.i2c.cr2.modify(|_, w| w.itbufen().clear_bit());
+                    }
+                }
+                State::Write {
+                    addr,
+                    bytes,
+                    pos,
+                    read,
+                }
+            }
+            State::Read {
+                addr,
+                mut buf,
+                mut pos,
+            } => {
+                let len = buf.len();
+                if sr1.sb().bit_is_set() {
+                    // Send the address in read mode
+                    self.i2c
+                        .dr
+                        .write(|w| unsafe { w.bits((u32::from(addr) << 1) + 1) });
+                } else if sr1.addr().bit_is_set() {
+                    match buf {
+                        ReadBuf::Dma(_) => {
+                            // The DMA reads all bytes, the NACK is generated after the last one
+                            self.i2c.sr2.read();
+                        }
+                        ReadBuf::Interrupt(_) if len == 1 => {
+                            // NACK and STOP after the only byte
+                            self.i2c.cr1.modify(|_, w| w.ack().clear_bit());
+                            self.i2c.sr2.read();
+                            self.i2c.cr1.modify(|_, w| w.stop().set_bit());
+                        }
+                        ReadBuf::Interrupt(_) if len == 2 => {
+                            // NACK the second byte, both bytes are read once BTF is set
+                            self.i2c.sr2.read();
+                            self.i2c.cr1.modify(|_, w| w.ack().clear_bit());
+                            self.i2c.cr2.modify(|_, w| w.itbufen().clear_bit());
+                        }
+                        ReadBuf::Interrupt(_) => {
+                            self.i2c.sr2.read();
+                            if len == 3 {
+                                self.i2c.cr2.modify(|_, w| w.itbufen().clear_bit());
+                            }
+                        }
+                    }
+                } else if let ReadBuf::Interrupt(ref mut buffer) = buf {
+                    let remaining = len - pos;
+                    if sr1.btf().bit_is_set() && remaining == 3 {
+                        // Bytes N-2 and N-1 have been received, NACK the last one
+                        self.i2c.cr1.modify(|_, w| w.ack().clear_bit());
+                        buffer[pos] = self.i2c.dr.read().bits() as u8;
+                        pos += 1;
+                    } else if sr1.btf().bit_is_set() && remaining == 2 {
+                        self.i2c
+                            .cr1
+                            .modify(|_, w| w.stop().set_bit().pos().clear_bit());
+                        buffer[pos] = self.i2c.dr.read().bits() as u8;
+                        buffer[pos + 1] = self.i2c.dr.read().bits() as u8;
+                        pos += 2;
+                    } else if sr1.rx_ne().bit_is_set() && (remaining > 3 || len == 1) {
+                        buffer[pos] = self.i2c.dr.read().bits() as u8;
+                        pos += 1;
+                        if len - pos == 3 {
+                            // The last three bytes are read once BTF is set
+                            self.i2c.cr2.modify(|_, w| w.itbufen().clear_bit());
+                        }
+                    }
+                    if pos == len {
+                        self.disable_interrupts();
+                        self.state = State::Done(buf.into_buffer());
+                        return;
+                    }
+                }
+                State::Read { addr, buf, pos }
+            }
+            state => state,
+        };
+    }
+
+    /// Aborts the current transfer on bus errors, must be called from the I2C error interrupt
+    /// handler
+    pub fn handle_error(&mut self) {
+        if let Err(error) = check_and_clear_error_flags(&self.i2c) {
+            let buffer = match mem::replace(&mut self.state, State::Idle) {
+                State::Write { read, .. } => read.and_then(ReadBuf::into_buffer),
+                State::Read { buf, .. } => buf.into_buffer(),
+                state => {
+                    self.state = state;
+                    return;
+                }
+            };
+
+            if error == Error::NACK {
+                self.i2c.cr1.modify(|_, w| w.stop().set_bit());
+            }
+            self.disable_interrupts();
+            self.state = State::Failed(TransferError { error, buffer });
+        }
+    }
+
+    /// Finishes a DMA read, must be called from the DMA transfer complete interrupt handler
+    pub fn handle_dma_complete(&mut self) {
+        if let State::Read {
+            buf: ReadBuf::Dma(_),
+            ..
+        } = self.state
+        {
+            self.i2c.cr1.modify(|_, w| w.stop().set_bit());
+            self.disable_interrupts();
+            self.state = State::Done(None);
+        }
+    }
+
+    /// Releases the I2C peripheral and associated pins
+    ///
+    /// # Panics
+    ///
+    /// * When a transfer is in progress.
+    pub fn release(self) -> (I2C, PINS) {
+        self.into_blocking().release()
+    }
+
+    fn start(&mut self, state: State) {
+        assert!(!self.is_busy(), "Transfer in progress");
+
+        let read = match &state {
+            State::Read { buf, .. } => Some(buf),
+            State::Write { read, .. } => read.as_ref(),
+            _ => None,
+        };
+        if let Some(buf) = read {
+            // Reads need at least one byte, DMA reads at least two
+            let min_len = if let ReadBuf::Dma(_) = buf { 2 } else { 1 };
+            assert!(buf.len() >= min_len, "Read buffer too short");
+        }
+
+        // DMA reads don't need the buffer interrupts, the DMA requests are raised regardless
+        let dma_read = matches!(
+            state,
+            State::Read {
+                buf: ReadBuf::Dma(_),
+                ..
+            }
+        );
+        if let State::Read { buf, .. } = &state {
+            self.setup_read(buf);
+        }
+        self.state = state;
+
+        self.i2c.cr2.modify(|_, w| {
+            w.itevten()
+                .set_bit()
+                .itbufen()
+                .bit(!dma_read)
+                .iterren()
+                .set_bit()
+        });
+        self.i2c.cr1.modify(|_, w| w.start().set_bit());
+    }
+
+    fn finish_write(&mut self, addr: u8, read: Option<ReadBuf>) {
+        match read {
+            Some(buf) => {
+                // Repeated start for the read
+                self.setup_read(&buf);
+                let dma_read = matches!(buf, ReadBuf::Dma(_));
+                self.i2c.cr2.modify(|_, w| w.itbufen().bit(!dma_read));
+                self.i2c.cr1.modify(|_, w| w.start().set_bit());
+                self.state = State::Read { addr, buf, pos: 0 };
+            }
+            None => {
+                self.i2c.cr1.modify(|_, w| w.stop().set_bit());
+                self.disable_interrupts();
+                self.state = State::Done(None);
+            }
+        }
+    }
+
+    fn setup_read(&self, buf: &ReadBuf) {
+        let len = buf.len();
+        if let ReadBuf::Dma(_) = buf {
+            self.i2c
+                .cr1
+                .modify(|_, w| w.ack().set_bit().pos().clear_bit());
+            self.i2c
+                .cr2
+                .modify(|_, w| w.dmaen().set_bit().last().set_bit());
+        } else {
+            self.i2c
+                .cr1
+                .modify(|_, w| w.ack().bit(len > 1).pos().bit(len == 2));
+            self.i2c
+                .cr2
+                .modify(|_, w| w.dmaen().clear_bit().last().clear_bit());
+        }
+    }
+
+    fn disable_interrupts(&self) {
+        self.i2c.cr2.modify(|_, w| {
+            w.itevten()
+                .clear_bit()
+                .itbufen()
+                .clear_bit()
+                .iterren()
+                .clear_bit()
+                .dmaen()
+                .clear_bit()
+                .last()
+                .clear_bit()
+        });
+    }
+}
@@ -1,8 +1,14 @@
 use core::ops::Deref;
+use cortex_m::peripheral::DWT;
 use embedded_hal::blocking::i2c::{Read, Write, WriteRead};
+use embedded_hal::digital::v2::{InputPin, OutputPin};
 
 use crate::{bb, pac::i2c1};
 
+pub mod interrupt;
+pub mod slave;
+pub mod smbus;
+
 #[cfg(any(
     feature = "stm32f401",
     feature = "stm32f405",
@@ -122,6 +128,46 @@ use crate::gpio::gpiob::PB4;
 ))]
 use crate::gpio::gpiob::{PB10, PB6, PB7, PB8, PB9};
 
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::gpio::gpioa::PA9;
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::gpio::gpiob::{PB12, PB5};
+
 #[cfg(any(feature = "stm32f446"))]
 use crate::gpio::gpioc::PC12;
 #[cfg(any(
@@ -185,15 +231,22 @@ use crate::gpio::gpioh::{PH4, PH5, PH7, PH8};
     feature = "stm32f423"
 ))]
 use crate::gpio::AF9;
-use crate::gpio::{AlternateOD, AF4};
+use crate::gpio::{AlternateOD, AsOpenDrainOutput, AF4};
 
 use crate::rcc::Clocks;
-use crate::time::{Hertz, KiloHertz, U32Ext};
+use crate::time::{Hertz, KiloHertz, MilliSeconds, U32Ext};
 
 /// I2C abstraction
 pub struct I2c<I2C: Instance, PINS> {
     i2c: I2C,
     pins: PINS,
+    clocks: Clocks,
+    /// Maximum number of SYSCLK cycles per transaction
+    timeout: Option<u64>,
+    /// SYSCLK cycles spent in the current transaction
+    elapsed: u64,
+    /// Cycle count at the last status register poll
+    last_poll: u32,
 }
 
 #[cfg(any(feature = "stm32f413", feature = "stm32f423",))]
@@ -214,6 +267,52 @@ where
 {
 }
 
+/// SMBALERT pin, see [`smbus::SmBus`]
+pub trait PinSmba<I2c> {}
+
+impl<I2c, SCL, SDA, SMBA> Pins<I2c> for (SCL, SDA, SMBA)
+where
+    SCL: PinScl<I2c>,
+    SDA: PinSda<I2c>,
+    SMBA: PinSmba<I2c>,
+{
+}
+
+/// Pins whose SCL and SDA lines can be driven as GPIOs by [`I2c::recover_bus`]
+pub trait BusPins {
+    type Scl: AsOpenDrainOutput;
+    type Sda: AsOpenDrainOutput;
+
+    /// Returns the SCL and SDA pins
+    fn scl_sda(&mut self) -> (&mut Self::Scl, &mut Self::Sda);
+}
+
+impl<SCL, SDA> BusPins for (SCL, SDA)
+where
+    SCL: AsOpenDrainOutput,
+    SDA: AsOpenDrainOutput,
+{
+    type Scl = SCL;
+    type Sda = SDA;
+
+    fn scl_sda(&mut self) -> (&mut SCL, &mut SDA) {
+        (&mut self.0, &mut self.1)
+    }
+}
+
+impl<SCL, SDA, SMBA> BusPins for (SCL, SDA, SMBA)
+where
+    SCL: AsOpenDrainOutput,
+    SDA: AsOpenDrainOutput,
+{
+    type Scl = SCL;
+    type Sda = SDA;
+
+    fn scl_sda(&mut self) -> (&mut SCL, &mut SDA) {
+        (&mut self.0, &mut self.1)
+    }
+}
+
 #[cfg(any(
     feature = "stm32f401",
     feature = "stm32f405",
@@ -273,6 +372,26 @@ impl PinSda<I2C1> for PB7<AlternateOD<AF4>> {}
     feature = "stm32f469",
     feature = "stm32f479"
 ))]
+impl PinSmba<I2C1> for PB5<AlternateOD<AF4>> {}
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
 impl PinScl<I2C1> for PB8<AlternateOD<AF4>> {}
 #[cfg(any(
     feature = "stm32f401",
@@ -334,6 +453,26 @@ impl PinSda<I2C2> for PB9<AlternateOD<AF9>> {}
     feature = "stm32f479"
 ))]
 impl PinScl<I2C2> for PB10<AlternateOD<AF4>> {}
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinSmba<I2C2> for PB12<AlternateOD<AF4>> {}
 #[cfg(any(
     feature = "stm32f405",
     feature = "stm32f407",
@@ -435,6 +574,25 @@ impl PinSda<I2C2> for PH5<AlternateOD<AF4>> {}
     feature = "stm32f479"
 ))]
 impl PinScl<I2C3> for PA8<AlternateOD<AF4>> {}
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinSmba<I2C3> for PA9<AlternateOD<AF4>> {}
 #[cfg(any(feature = "stm32f446"))]
 impl PinSda<I2C3> for PB4<AlternateOD<AF4>> {}
 #[cfg(any(
@@ -548,14 +706,26 @@ impl PinScl<FMPI2C1> for PF15<AlternateOD<AF4>> {}
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub enum Error {
+    /// Overrun or underrun (OVR)
     OVERRUN,
+    /// Acknowledge failure (AF)
     NACK,
+    /// SMBus clock low timeout (TIMEOUT) reported by the peripheral
+    ///
+    /// See [`TransactionTimeout`](Error::TransactionTimeout) for the timeout set with
+    /// `set_timeout`.
     TIMEOUT,
-    // Note: The BUS error type is not currently returned, but is maintained for backwards
-    // compatibility.
+    /// Misplaced START or STOP condition (BERR)
+    ///
+    /// Only returned in slave mode and by `recover_bus`, the master ignores bus errors as
+    /// recommended by the errata.
     BUS,
+    /// PEC mismatch (PECERR)
     CRC,
+    /// Arbitration lost (ARLO)
     ARBITRATION,
+    /// The transaction took longer than the timeout set with `set_timeout`
+    TransactionTimeout,
 }
 
 mod private {
@@ -564,6 +734,8 @@ mod private {
 
 // Implemented by all I2C instances
 pub trait Instance: private::Sealed + Deref<Target = i2c1::RegisterBlock> {
+    #[doc(hidden)]
+    fn ptr() -> *const i2c1::RegisterBlock;
     #[doc(hidden)]
     unsafe fn enable_clock(rcc: &crate::stm32::rcc::RegisterBlock);
 }
@@ -575,6 +747,10 @@ macro_rules! i2c {
         $(
             impl private::Sealed for $I2C {}
             impl Instance for $I2C {
+                fn ptr() -> *const i2c1::RegisterBlock {
+                    $I2C::ptr() as *const _
+                }
+
                 unsafe fn enable_clock(rcc: &crate::stm32::rcc::RegisterBlock) {
                     bb::set(&rcc.$apbXenr, $en_bit);
 
@@ -681,30 +857,7 @@ impl<PINS> FMPI2c<FMPI2C1, PINS> {
     where
         PINS: Pins<FMPI2C1>,
     {
-        unsafe {
-            const EN_BIT: u8 = 24;
-            const RESET_BIT: u8 = 24;
-            const CLKSEL_0: u8 = 22;
-            const CLKSEL_1: u8 = 23;
-
-            // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
-            let rcc = &(*RCC::ptr());
-
-            // Enable and reset clock.
-            bb::set(&rcc.apb1enr, EN_BIT);
-
-            // Stall the pipeline to work around erratum 2.1.13 (DM00037591)
-            cortex_m::asm::dsb();
-
-            bb::set(&rcc.apb1rstr, RESET_BIT);
-            bb::clear(&rcc.apb1rstr, RESET_BIT);
-
-            // Select source clock. This is suboptimal, we're doing two writes to select a field,
-            // but we can do this because all combinations are valid and the intermediate state is
-            // already the state we want or the reset state.
-            bb::clear(&rcc.dckcfgr2, CLKSEL_0);
-            bb::set(&rcc.dckcfgr2, CLKSEL_1);
-        }
+        fmpi2c_enable_clock();
 
         let i2c = FMPI2c { i2c, pins };
         i2c.i2c_init(speed);
@@ -712,6 +865,34 @@ impl<PINS> FMPI2c<FMPI2C1, PINS> {
     }
 }
 
+#[cfg(any(feature = "stm32f413", feature = "stm32f423",))]
+fn fmpi2c_enable_clock() {
+    unsafe {
+        const EN_BIT: u8 = 24;
+        const RESET_BIT: u8 = 24;
+        const CLKSEL_0: u8 = 22;
+        const CLKSEL_1: u8 = 23;
+
+        // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+        let rcc = &(*RCC::ptr());
+
+        // Enable and reset clock.
+        bb::set(&rcc.apb1enr, EN_BIT);
+
+        // Stall the pipeline to work around erratum 2.1.13 (DM00037591)
+        cortex_m::asm::dsb();
+
+        bb::set(&rcc.apb1rstr, RESET_BIT);
+        bb::clear(&rcc.apb1rstr, RESET_BIT);
+
+        // Select source clock. This is suboptimal, we're doing two writes to select a field,
+        // but we can do this because all combinations are valid and the intermediate state is
+        // already the state we want or the reset state.
+        bb::clear(&rcc.dckcfgr2, CLKSEL_0);
+        bb::set(&rcc.dckcfgr2, CLKSEL_1);
+    }
+}
+
 impl<I2C, PINS> I2c<I2C, PINS>
 where
     I2C: Instance,
@@ -728,11 +909,64 @@ where
             I2C::enable_clock(&rcc);
         }
 
-        let i2c = I2c { i2c, pins };
+        let i2c = I2c {
+            i2c,
+            pins,
+            clocks,
+            timeout: None,
+            elapsed: 0,
+            last_poll: 0,
+        };
         i2c.i2c_init(speed, clocks.pclk1());
         i2c
     }
 
+    /// Sets the maximum duration of a transaction, `None` waits forever (the default)
+    ///
+    /// A transaction that doesn't finish in time, e.g. because a slave holds SDA low, returns
+    /// [`Error::TransactionTimeout`], the bus can then be freed with `recover_bus`. The time is measured with
+    /// the DWT cycle counter, which has to be enabled, e.g. with
+    /// [`DwtExt::constrain`](crate::dwt::DwtExt::constrain).
+    ///
+    /// # Panics
+    ///
+    /// * When `timeout` is `Some` and the DWT cycle counter is disabled.
+    pub fn set_timeout(&mut self, timeout: Option<MilliSeconds>) {
+        if timeout.is_some() {
+            // NOTE(unsafe) read only access to the DWT control register, CYCCNTENA is bit 0
+            let enabled = unsafe { (*DWT::PTR).ctrl.read() & 1 != 0 };
+            assert!(enabled, "DWT cycle counter disabled");
+        }
+        let cycles_per_ms = u64::from(self.clocks.sysclk().0 / 1_000);
+        self.timeout = timeout.map(|ms| u64::from(ms.0) * cycles_per_ms);
+    }
+
+    /// Starts measuring the duration of a new transaction
+    fn start_transaction(&mut self) {
+        self.elapsed = 0;
+        self.last_poll = DWT::cycle_count();
+    }
+
+    /// Polls the status registers until `done` returns true or the transaction timed out
+    fn wait<F>(&mut self, mut done: F) -> Result<(), Error>
+    where
+        F: FnMut(&I2C) -> Result<bool, Error>,
+    {
+        while !done(&self.i2c)? {
+            if let Some(timeout) = self.timeout {
+                // Accumulate the differences, the cycle counter wraps after a few seconds
+                let now = DWT::cycle_count();
+                self.elapsed += u64::from(now.wrapping_sub(self.last_poll));
+                self.last_poll = now;
+                if self.elapsed > timeout {
+                    return Err(Error::TransactionTimeout);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     fn i2c_init(&self, speed: KiloHertz, pclk: Hertz) {
         let speed: Hertz = speed.into();
 
@@ -801,56 +1035,145 @@ where
         self.i2c.cr1.modify(|_, w| w.pe().set_bit());
     }
 
-    fn check_and_clear_error_flags(&self) -> Result<i2c1::sr1::R, Error> {
-        // Note that flags should only be cleared once they have been registered. If flags are
-        // cleared otherwise, there may be an inherent race condition and flags may be missed.
-        let sr1 = self.i2c.sr1.read();
+    /// Converts the blocking driver into an interrupt driven one, see
+    /// [`interrupt::InterruptI2c`].
+    pub fn into_interrupt(self) -> interrupt::InterruptI2c<I2C, PINS> {
+        interrupt::InterruptI2c::new(self)
+    }
 
-        if sr1.timeout().bit_is_set() {
-            self.i2c.sr1.modify(|_, w| w.timeout().clear_bit());
-            return Err(Error::TIMEOUT);
-        }
+    /// Switches the peripheral to SMBus host mode, see [`smbus::SmBus`]
+    pub fn into_smbus(self) -> smbus::SmBus<I2C, PINS> {
+        smbus::SmBus::new(self)
+    }
 
-        if sr1.pecerr().bit_is_set() {
-            self.i2c.sr1.modify(|_, w| w.pecerr().clear_bit());
-            return Err(Error::CRC);
-        }
+    pub fn release(self) -> (I2C, PINS) {
+        (self.i2c, self.pins)
+    }
+}
 
-        if sr1.ovr().bit_is_set() {
-            self.i2c.sr1.modify(|_, w| w.ovr().clear_bit());
-            return Err(Error::OVERRUN);
-        }
+impl<I2C, PINS> I2c<I2C, PINS>
+where
+    I2C: Instance,
+    PINS: BusPins,
+{
+    /// Frees the bus after a slave was left in the middle of a transfer, e.g. because the MCU was
+    /// reset while reading from it
+    ///
+    /// The peripheral is disabled and SCL is driven as a GPIO for up to 9 clock pulses until the
+    /// slave releases SDA, followed by a STOP condition. The pins are then switched back to their
+    /// alternate function and the peripheral is reset, keeping its configuration and own
+    /// addresses. Returns [`Error::BUS`] if SDA is still held low.
+    pub fn recover_bus(&mut self) -> Result<(), Error> {
+        // A software reset clears the configuration. PE, START, STOP, PEC and SWRST of CR1 are
+        // not restored.
+        let cr1 = self.i2c.cr1.read().bits() & !(1 << 0 | 1 << 8 | 1 << 9 | 1 << 12 | 1 << 15);
+        let cr2 = self.i2c.cr2.read().bits();
+        let oar1 = self.i2c.oar1.read().bits();
+        let oar2 = self.i2c.oar2.read().bits();
+        let ccr = self.i2c.ccr.read().bits();
+        let trise = self.i2c.trise.read().bits();
 
-        if sr1.af().bit_is_set() {
-            self.i2c.sr1.modify(|_, w| w.af().clear_bit());
-            return Err(Error::NACK);
-        }
+        self.i2c.cr1.modify(|_, w| w.pe().clear_bit());
 
-        if sr1.arlo().bit_is_set() {
-            self.i2c.sr1.modify(|_, w| w.arlo().clear_bit());
-            return Err(Error::ARBITRATION);
-        }
+        // Half the period of a 100 kHz clock
+        let half_period = self.clocks.sysclk().0 / 200_000;
+        let (scl, sda) = self.pins.scl_sda();
+        let released = scl.with_open_drain_output(|scl| {
+            sda.with_open_drain_output(|sda| {
+                for _ in 0..9 {
+                    if sda.is_high().unwrap() {
+                        break;
+                    }
+
+                    scl.set_low().unwrap();
+                    cortex_m::asm::delay(half_period);
+                    scl.set_high().unwrap();
+                    cortex_m::asm::delay(half_period);
+                }
+
+                // STOP condition, SDA goes high while SCL is high
+                scl.set_low().unwrap();
+                cortex_m::asm::delay(half_period);
+                sda.set_low().unwrap();
+                cortex_m::asm::delay(half_period);
+                scl.set_high().unwrap();
+                cortex_m::asm::delay(half_period);
+                sda.set_high().unwrap();
+                cortex_m::asm::delay(half_period);
+
+                sda.is_high().unwrap()
+            })
+        });
+
+        // Clear the BUSY flag, which stays set after the bus got stuck
+        self.i2c.cr1.write(|w| w.swrst().set_bit());
+        self.i2c.cr1.write(|w| w.swrst().clear_bit());
 
-        // The errata indicates that BERR may be incorrectly detected. It recommends ignoring and
-        // clearing the BERR bit instead.
-        if sr1.berr().bit_is_set() {
-            self.i2c.sr1.modify(|_, w| w.berr().clear_bit());
+        self.i2c.cr2.write(|w| unsafe { w.bits(cr2) });
+        self.i2c.oar1.write(|w| unsafe { w.bits(oar1) });
+        self.i2c.oar2.write(|w| unsafe { w.bits(oar2) });
+        self.i2c.ccr.write(|w| unsafe { w.bits(ccr) });
+        self.i2c.trise.write(|w| unsafe { w.bits(trise) });
+        self.i2c.cr1.write(|w| unsafe { w.bits(cr1) });
+        self.i2c.cr1.modify(|_, w| w.pe().set_bit());
+
+        if released {
+            Ok(())
+        } else {
+            Err(Error::BUS)
         }
+    }
+}
+
+fn check_and_clear_error_flags(i2c: &i2c1::RegisterBlock) -> Result<i2c1::sr1::R, Error> {
+    // Note that flags should only be cleared once they have been registered. If flags are
+    // cleared otherwise, there may be an inherent race condition and flags may be missed.
+    let sr1 = i2c.sr1.read();
 
-        Ok(sr1)
+    if sr1.timeout().bit_is_set() {
+        i2c.sr1.modify(|_, w| w.timeout().clear_bit());
+        return Err(Error::TIMEOUT);
     }
 
-    pub fn release(self) -> (I2C, PINS) {
-        (self.i2c, self.pins)
+    if sr1.pecerr().bit_is_set() {
+        i2c.sr1.modify(|_, w| w.pecerr().clear_bit());
+        return Err(Error::CRC);
+    }
+
+    if sr1.ovr().bit_is_set() {
+        i2c.sr1.modify(|_, w| w.ovr().clear_bit());
+        return Err(Error::OVERRUN);
+    }
+
+    if sr1.af().bit_is_set() {
+        i2c.sr1.modify(|_, w| w.af().clear_bit());
+        return Err(Error::NACK);
     }
+
+    if sr1.arlo().bit_is_set() {
+        i2c.sr1.modify(|_, w| w.arlo().clear_bit());
+        return Err(Error::ARBITRATION);
+    }
+
+    // The errata indicates that BERR may be incorrectly detected. It recommends ignoring and
+    // clearing the BERR bit instead.
+    if sr1.berr().bit_is_set() {
+        i2c.sr1.modify(|_, w| w.berr().clear_bit());
+    }
+
+    Ok(sr1)
 }
 
 trait I2cCommon {
     fn write_bytes(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Error>;
 
-    fn send_byte(&self, byte: u8) -> Result<(), Error>;
+    fn start_read(&mut self, addr: u8) -> Result<(), Error>;
 
-    fn recv_byte(&self) -> Result<u8, Error>;
+    fn read_bytes(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Error>;
+
+    fn send_byte(&mut self, byte: u8) -> Result<(), Error>;
+
+    fn recv_byte(&mut self) -> Result<u8, Error>;
 }
 
 impl<I2C, PINS> I2cCommon for I2c<I2C, PINS>
@@ -862,15 +1185,15 @@ where
         self.i2c.cr1.modify(|_, w| w.start().set_bit());
 
         // Wait until START condition was generated
-        while self.check_and_clear_error_flags()?.sb().bit_is_clear() {}
+        self.wait(|i2c| Ok(check_and_clear_error_flags(i2c)?.sb().bit_is_set()))?;
 
         // Also wait until signalled we're master and everything is waiting for us
-        while {
-            self.check_and_clear_error_flags()?;
+        self.wait(|i2c| {
+            check_and_clear_error_flags(i2c)?;
 
-            let sr2 = self.i2c.sr2.read();
-            sr2.msl().bit_is_clear() && sr2.busy().bit_is_clear()
-        } {}
+            let sr2 = i2c.sr2.read();
+            Ok(sr2.msl().bit_is_set() || sr2.busy().bit_is_set())
+        })?;
 
         // Set up current address, we're trying to talk to
         self.i2c
@@ -878,13 +1201,13 @@ where
             .write(|w| unsafe { w.bits(u32::from(addr) << 1) });
 
         // Wait until address was sent
-        while {
+        self.wait(|i2c| {
             // Check for any I2C errors. If a NACK occurs, the ADDR bit will never be set.
-            let sr1 = self.check_and_clear_error_flags()?;
+            let sr1 = check_and_clear_error_flags(i2c)?;
 
             // Wait for the address to be acknowledged
-            sr1.addr().bit_is_clear()
-        } {}
+            Ok(sr1.addr().bit_is_set())
+        })?;
 
         // Clear condition by reading SR2
         self.i2c.sr2.read();
@@ -898,32 +1221,91 @@ where
         Ok(())
     }
 
-    fn send_byte(&self, byte: u8) -> Result<(), Error> {
+    fn start_read(&mut self, addr: u8) -> Result<(), Error> {
+        // Send a START condition and set ACK bit
+        self.i2c
+            .cr1
+            .modify(|_, w| w.start().set_bit().ack().set_bit());
+
+        // Wait until START condition was generated
+        self.wait(|i2c| Ok(i2c.sr1.read().sb().bit_is_set()))?;
+
+        // Also wait until signalled we're master and everything is waiting for us
+        self.wait(|i2c| {
+            let sr2 = i2c.sr2.read();
+            Ok(sr2.msl().bit_is_set() || sr2.busy().bit_is_set())
+        })?;
+
+        // Set up current address, we're trying to talk to
+        self.i2c
+            .dr
+            .write(|w| unsafe { w.bits((u32::from(addr) << 1) + 1) });
+
+        // Wait until address was sent
+        self.wait(|i2c| {
+            check_and_clear_error_flags(i2c)?;
+            Ok(i2c.sr1.read().addr().bit_is_set())
+        })?;
+
+        // Clear condition by reading SR2
+        self.i2c.sr2.read();
+
+        Ok(())
+    }
+
+    fn read_bytes(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Error> {
+        if let Some((last, buffer)) = buffer.split_last_mut() {
+            self.start_read(addr)?;
+
+            // Receive bytes into buffer
+            for c in buffer {
+                *c = self.recv_byte()?;
+            }
+
+            // Prepare to send NACK then STOP after next byte
+            self.i2c
+                .cr1
+                .modify(|_, w| w.ack().clear_bit().stop().set_bit());
+
+            // Receive last byte
+            *last = self.recv_byte()?;
+
+            // Wait for the STOP to be sent.
+            self.wait(|i2c| Ok(i2c.cr1.read().stop().bit_is_clear()))?;
+
+            // Fallthrough is success
+            Ok(())
+        } else {
+            Err(Error::OVERRUN)
+        }
+    }
+
+    fn send_byte(&mut self, byte: u8) -> Result<(), Error> {
         // Wait until we're ready for sending
-        while {
+        self.wait(|i2c| {
             // Check for any I2C errors. If a NACK occurs, the ADDR bit will never be set.
-            self.check_and_clear_error_flags()?.tx_e().bit_is_clear()
-        } {}
+            Ok(check_and_clear_error_flags(i2c)?.tx_e().bit_is_set())
+        })?;
 
         // Push out a byte of data
         self.i2c.dr.write(|w| unsafe { w.bits(u32::from(byte)) });
 
         // Wait until byte is transferred
-        while {
+        self.wait(|i2c| {
             // Check for any potential error conditions.
-            self.check_and_clear_error_flags()?.btf().bit_is_clear()
-        } {}
+            Ok(check_and_clear_error_flags(i2c)?.btf().bit_is_set())
+        })?;
 
         Ok(())
     }
 
-    fn recv_byte(&self) -> Result<u8, Error> {
-        while {
+    fn recv_byte(&mut self) -> Result<u8, Error> {
+        self.wait(|i2c| {
             // Check for any potential error conditions.
-            self.check_and_clear_error_flags()?;
+            check_and_clear_error_flags(i2c)?;
 
-            self.i2c.sr1.read().rx_ne().bit_is_clear()
-        } {}
+            Ok(i2c.sr1.read().rx_ne().bit_is_set())
+        })?;
 
         let value = self.i2c.dr.read().bits() as u8;
         Ok(value)
@@ -937,8 +1319,9 @@ where
     type Error = Error;
 
     fn write_read(&mut self, addr: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.start_transaction();
         self.write_bytes(addr, bytes)?;
-        self.read(addr, buffer)?;
+        self.read_bytes(addr, buffer)?;
 
         Ok(())
     }
@@ -951,13 +1334,14 @@ where
     type Error = Error;
 
     fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.start_transaction();
         self.write_bytes(addr, bytes)?;
 
         // Send a STOP condition
         self.i2c.cr1.modify(|_, w| w.stop().set_bit());
 
         // Wait for STOP condition to transmit.
-        while self.i2c.cr1.read().stop().bit_is_set() {}
+        self.wait(|i2c| Ok(i2c.cr1.read().stop().bit_is_clear()))?;
 
         // Fallthrough is success
         Ok(())
@@ -971,56 +1355,8 @@ where
     type Error = Error;
 
     fn read(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
-        if let Some((last, buffer)) = buffer.split_last_mut() {
-            // Send a START condition and set ACK bit
-            self.i2c
-                .cr1
-                .modify(|_, w| w.start().set_bit().ack().set_bit());
-
-            // Wait until START condition was generated
-            while self.i2c.sr1.read().sb().bit_is_clear() {}
-
-            // Also wait until signalled we're master and everything is waiting for us
-            while {
-                let sr2 = self.i2c.sr2.read();
-                sr2.msl().bit_is_clear() && sr2.busy().bit_is_clear()
-            } {}
-
-            // Set up current address, we're trying to talk to
-            self.i2c
-                .dr
-                .write(|w| unsafe { w.bits((u32::from(addr) << 1) + 1) });
-
-            // Wait until address was sent
-            while {
-                self.check_and_clear_error_flags()?;
-                self.i2c.sr1.read().addr().bit_is_clear()
-            } {}
-
-            // Clear condition by reading SR2
-            self.i2c.sr2.read();
-
-            // Receive bytes into buffer
-            for c in buffer {
-                *c = self.recv_byte()?;
-            }
-
-            // Prepare to send NACK then STOP after next byte
-            self.i2c
-                .cr1
-                .modify(|_, w| w.ack().clear_bit().stop().set_bit());
-
-            // Receive last byte
-            *last = self.recv_byte()?;
-
-            // Wait for the STOP to be sent.
-            while self.i2c.cr1.read().stop().bit_is_set() {}
-
-            // Fallthrough is success
-            Ok(())
-        } else {
-            Err(Error::OVERRUN)
-        }
+        self.start_transaction();
+        self.read_bytes(addr, buffer)
     }
 }
 
@@ -1030,59 +1366,7 @@ where
     I2C: Deref<Target = fmpi2c1::RegisterBlock>,
 {
     fn i2c_init(&self, speed: KiloHertz) {
-        use core::cmp;
-
-        // Make sure the I2C unit is disabled so we can configure it
-        self.i2c.cr1.modify(|_, w| w.pe().clear_bit());
-
-        // Calculate settings for I2C speed modes
-        let presc;
-        let scldel;
-        let sdadel;
-        let sclh;
-        let scll;
-
-        // We're using the HSI clock to keep things simple so this is going to be always 16 MHz
-        const FREQ: u32 = 16_000_000;
-
-        // Normal I2C speeds use a different scaling than fast mode below and fast mode+ even more
-        // below
-        if speed <= 100.khz() {
-            presc = 3;
-            scll = cmp::max((((FREQ >> presc) >> 1) / speed.0) - 1, 255) as u8;
-            sclh = scll - 4;
-            sdadel = 2;
-            scldel = 4;
-        } else if speed <= 400.khz() {
-            presc = 1;
-            scll = cmp::max((((FREQ >> presc) >> 1) / speed.0) - 1, 255) as u8;
-            sclh = scll - 6;
-            sdadel = 2;
-            scldel = 3;
-        } else {
-            presc = 0;
-            scll = cmp::max((((FREQ >> presc) >> 1) / speed.0) - 4, 255) as u8;
-            sclh = scll - 2;
-            sdadel = 0;
-            scldel = 2;
-        }
-
-        // Enable I2C signal generator, and configure I2C for configured speed
-        self.i2c.timingr.write(|w| {
-            w.presc()
-                .bits(presc)
-                .scldel()
-                .bits(scldel)
-                .sdadel()
-                .bits(sdadel)
-                .sclh()
-                .bits(sclh)
-                .scll()
-                .bits(scll)
-        });
-
-        // Enable the I2C processing
-        self.i2c.cr1.modify(|_, w| w.pe().set_bit());
+        fmpi2c_init(&self.i2c, speed);
     }
 
     pub fn release(self) -> (I2C, PINS) {
@@ -1128,6 +1412,63 @@ where
     }
 }
 
+#[cfg(any(feature = "stm32f413", feature = "stm32f423",))]
+fn fmpi2c_init(i2c: &fmpi2c1::RegisterBlock, speed: KiloHertz) {
+    use core::cmp;
+
+    // Make sure the I2C unit is disabled so we can configure it
+    i2c.cr1.modify(|_, w| w.pe().clear_bit());
+
+    // Calculate settings for I2C speed modes
+    let presc;
+    let scldel;
+    let sdadel;
+    let sclh;
+    let scll;
+
+    // We're using the HSI clock to keep things simple so this is going to be always 16 MHz
+    const FREQ: u32 = 16_000_000;
+
+    // Normal I2C speeds use a different scaling than fast mode below and fast mode+ even more
+    // below
+    if speed <= 100.khz() {
+        presc = 3;
+        scll = cmp::max((((FREQ >> presc) >> 1) / speed.0) - 1, 255) as u8;
+        sclh = scll - 4;
+        sdadel = 2;
+        scldel = 4;
+    } else if speed <= 400.khz() {
+        presc = 1;
+        scll = cmp::max((((FREQ >> presc) >> 1) / speed.0) - 1, 255) as u8;
+        sclh = scll - 6;
+        sdadel = 2;
+        scldel = 3;
+    } else {
+        presc = 0;
+        scll = cmp::max((((FREQ >> presc) >> 1) / speed.0) - 4, 255) as u8;
+        sclh = scll - 2;
+        sdadel = 0;
+        scldel = 2;
+    }
+
+    // Enable I2C signal generator, and configure I2C for configured speed
+    i2c.timingr.write(|w| {
+        w.presc()
+            .bits(presc)
+            .scldel()
+            .bits(scldel)
+            .sdadel()
+            .bits(sdadel)
+            .sclh()
+            .bits(sclh)
+            .scll()
+            .bits(scll)
+    });
+
+    // Enable the I2C processing
+    i2c.cr1.modify(|_, w| w.pe().set_bit());
+}
+
 #[cfg(any(feature = "stm32f413", feature = "stm32f423",))]
 impl<I2C, PINS> WriteRead for FMPI2c<I2C, PINS>
 where
@@ -0,0 +1,434 @@
+//! I2C slave (target) mode
+//!
+//! [`I2cSlave`] lets an `I2C1..I2C3` peripheral answer transfers started by another master on the
+//! bus, [`FMPI2cSlave`] does the same for the `FMPI2C1` peripheral. Both respond to one or two
+//! 7-bit own addresses and optionally to the general call address.
+//!
+//! The bus is driven by the master, so the driver reports what happens on it as [`Event`]s
+//! returned by `poll`. `poll` can be called in a loop or from the I2C event and error interrupt
+//! handlers after calling `listen`.
+//!
+//! ```ignore
+//! let config = SlaveConfig::new(0x42).secondary_address(0x43);
+//! let mut slave = I2cSlave::new(dp.I2C1, (scl, sda), config, clocks);
+//!
+//! loop {
+//!     match block!(slave.poll()) {
+//!         Ok(Event::AddressMatch { direction: Direction::Write, .. }) => pos = 0,
+//!         Ok(Event::Received(byte)) => {
+//!             regs[pos] = byte;
+//!             pos += 1;
+//!         }
+//!         Ok(Event::TransmitRequest) => {
+//!             slave.write(regs[pos]);
+//!             pos += 1;
+//!         }
+//!         _ => {}
+//!     }
+//! }
+//! ```
+//!
+//! While clock stretching is enabled (the default) the slave holds SCL low until a received byte
+//! has been read or the byte requested by [`Event::TransmitRequest`] has been written, so the
+//! master waits for the application. With clock stretching disabled the application has to keep
+//! up with the bus clock or overruns will be reported.
+
+use super::{Error, Instance, Pins};
+use crate::rcc::Clocks;
+use crate::stm32::RCC;
+
+#[cfg(any(feature = "stm32f413", feature = "stm32f423",))]
+use super::{fmpi2c1, fmpi2c_enable_clock, fmpi2c_init};
+#[cfg(any(feature = "stm32f413", feature = "stm32f423",))]
+use crate::stm32::FMPI2C1;
+#[cfg(any(feature = "stm32f413", feature = "stm32f423",))]
+use crate::time::KiloHertz;
+#[cfg(any(feature = "stm32f413", feature = "stm32f423",))]
+use core::ops::Deref;
+
+/// Slave configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlaveConfig {
+    pub(crate) address: u8,
+    pub(crate) secondary_address: Option<u8>,
+    pub(crate) general_call: bool,
+    pub(crate) clock_stretching: bool,
+}
+
+impl SlaveConfig {
+    /// Creates a configuration responding to the 7-bit `address`
+    pub fn new(address: u8) -> Self {
+        assert!(address < 0x80);
+
+        SlaveConfig {
+            address,
+            secondary_address: None,
+            general_call: false,
+            clock_stretching: true,
+        }
+    }
+
+    /// Also respond to a second 7-bit address (dual addressing mode)
+    pub fn secondary_address(mut self, address: u8) -> Self {
+        assert!(address < 0x80);
+        self.secondary_address = Some(address);
+        self
+    }
+
+    /// Respond to the general call address `0x00`
+    pub fn general_call(mut self, general_call: bool) -> Self {
+        self.general_call = general_call;
+        self
+    }
+
+    /// Enable or disable clock stretching, enabled by default
+    pub fn clock_stretching(mut self, clock_stretching: bool) -> Self {
+        self.clock_stretching = clock_stretching;
+        self
+    }
+}
+
+/// Direction of a transfer, as seen from the master
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// The master reads, the slave transmits
+    Read,
+    /// The master writes, the slave receives
+    Write,
+}
+
+/// Which of the own addresses was matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchedAddress {
+    /// The address passed to [`SlaveConfig::new`]
+    Primary,
+    /// The address passed to [`SlaveConfig::secondary_address`]
+    Secondary,
+    /// The general call address
+    GeneralCall,
+}
+
+/// Something happened on the bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// The master started a transfer to one of our addresses
+    AddressMatch {
+        address: MatchedAddress,
+        direction: Direction,
+    },
+    /// The master wrote a byte
+    Received(u8),
+    /// The master wants to read a byte, answer it with `write`
+    TransmitRequest,
+    /// The transfer has ended
+    Stop,
+}
+
+/// I2C peripheral operating in slave mode
+pub struct I2cSlave<I2C: Instance, PINS> {
+    i2c: I2C,
+    pins: PINS,
+}
+
+impl<I2C, PINS> I2cSlave<I2C, PINS>
+where
+    I2C: Instance,
+{
+    pub fn new(i2c: I2C, pins: PINS, config: SlaveConfig, clocks: Clocks) -> Self
+    where
+        PINS: Pins<I2C>,
+    {
+        unsafe {
+            // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+            let rcc = &(*RCC::ptr());
+
+            // Enable and reset clock.
+            I2C::enable_clock(rcc);
+        }
+
+        // Make sure the I2C unit is disabled so we can configure it
+        i2c.cr1.modify(|_, w| w.pe().clear_bit());
+
+        // The peripheral clock frequency is needed for the data setup time, even in slave mode
+        let freq = clocks.pclk1().0 / 1_000_000;
+        assert!((2..=50).contains(&freq));
+        i2c.cr2.write(|w| unsafe { w.freq().bits(freq as u8) });
+
+        // Bit 14 of OAR1 has to be kept at 1 by software
+        i2c.oar1
+            .write(|w| unsafe { w.bits(1 << 14).add().bits(u16::from(config.address) << 1) });
+        i2c.oar2.write(|w| match config.secondary_address {
+            Some(address) => w.endual().set_bit().add2().bits(address),
+            None => w.endual().clear_bit(),
+        });
+
+        i2c.cr1.modify(|_, w| {
+            w.engc()
+                .bit(config.general_call)
+                .nostretch()
+                .bit(!config.clock_stretching)
+                .pe()
+                .set_bit()
+        });
+
+        // ACK can only be set once the peripheral is enabled
+        i2c.cr1.modify(|_, w| w.ack().set_bit());
+
+        I2cSlave { i2c, pins }
+    }
+
+    /// Enables the event, buffer and error interrupts
+    ///
+    /// The event interrupt stays pending after an [`Event::TransmitRequest`] until `write` has
+    /// been called.
+    pub fn listen(&mut self) {
+        self.i2c.cr2.modify(|_, w| {
+            w.itevten()
+                .set_bit()
+                .itbufen()
+                .set_bit()
+                .iterren()
+                .set_bit()
+        });
+    }
+
+    /// Disables the event, buffer and error interrupts
+    pub fn unlisten(&mut self) {
+        self.i2c.cr2.modify(|_, w| {
+            w.itevten()
+                .clear_bit()
+                .itbufen()
+                .clear_bit()
+                .iterren()
+                .clear_bit()
+        });
+    }
+
+    /// Returns the next event on the bus
+    pub fn poll(&mut self) -> nb::Result<Event, Error> {
+        let sr1 = self.i2c.sr1.read();
+
+        if sr1.berr().bit_is_set() {
+            self.i2c.sr1.modify(|_, w| w.berr().clear_bit());
+            return Err(nb::Error::Other(Error::BUS));
+        }
+
+        if sr1.ovr().bit_is_set() {
+            self.i2c.sr1.modify(|_, w| w.ovr().clear_bit());
+            return Err(nb::Error::Other(Error::OVERRUN));
+        }
+
+        if sr1.addr().bit_is_set() {
+            // Reading SR2 after SR1 clears ADDR
+            let sr2 = self.i2c.sr2.read();
+
+            let address = if sr2.gencall().bit_is_set() {
+                MatchedAddress::GeneralCall
+            } else if sr2.dualf().bit_is_set() {
+                MatchedAddress::Secondary
+            } else {
+                MatchedAddress::Primary
+            };
+            let direction = if sr2.tra().bit_is_set() {
+                Direction::Read
+            } else {
+                Direction::Write
+            };
+
+            return Ok(Event::AddressMatch { address, direction });
+        }
+
+        if sr1.rx_ne().bit_is_set() {
+            return Ok(Event::Received(self.i2c.dr.read().bits() as u8));
+        }
+
+        if sr1.stopf().bit_is_set() {
+            // STOPF is cleared by reading SR1 followed by a write to CR1
+            self.i2c.cr1.modify(|_, w| w);
+            return Ok(Event::Stop);
+        }
+
+        // The master ends a read by not acknowledging the last byte, there is no STOP detection
+        // in slave transmitter mode
+        if sr1.af().bit_is_set() {
+            self.i2c.sr1.modify(|_, w| w.af().clear_bit());
+            return Ok(Event::Stop);
+        }
+
+        if sr1.tx_e().bit_is_set() {
+            return Ok(Event::TransmitRequest);
+        }
+
+        Err(nb::Error::WouldBlock)
+    }
+
+    /// Sends a byte in response to [`Event::TransmitRequest`]
+    pub fn write(&mut self, byte: u8) {
+        self.i2c.dr.write(|w| unsafe { w.bits(u32::from(byte)) });
+    }
+
+    pub fn release(self) -> (I2C, PINS) {
+        self.i2c.cr1.modify(|_, w| w.pe().clear_bit());
+        (self.i2c, self.pins)
+    }
+}
+
+/// FMPI2C peripheral operating in slave mode
+#[cfg(any(feature = "stm32f413", feature = "stm32f423",))]
+pub struct FMPI2cSlave<I2C, PINS> {
+    i2c: I2C,
+    pins: PINS,
+}
+
+#[cfg(any(feature = "stm32f413", feature = "stm32f423",))]
+impl<PINS> FMPI2cSlave<FMPI2C1, PINS> {
+    /// Creates the slave, `speed` is the bus speed used by the master and determines the data
+    /// setup and hold times
+    pub fn new(i2c: FMPI2C1, pins: PINS, config: SlaveConfig, speed: KiloHertz) -> Self
+    where
+        PINS: Pins<FMPI2C1>,
+    {
+        fmpi2c_enable_clock();
+        fmpi2c_init(&i2c, speed);
+
+        // NOSTRETCH can only be changed while the peripheral is disabled
+        i2c.cr1.modify(|_, w| w.pe().clear_bit());
+
+        i2c.oar1.write(|w| w.oa1en().clear_bit());
+        i2c.oar1.write(|w| unsafe {
+            w.oa11_7()
+                .bits(config.address)
+                .oa1mode()
+                .clear_bit()
+                .oa1en()
+                .set_bit()
+        });
+        i2c.oar2.write(|w| w.oa2en().clear_bit());
+        if let Some(address) = config.secondary_address {
+            i2c.oar2
+                .write(|w| w.oa2().bits(address).oa2msk().no_mask().oa2en().set_bit());
+        }
+
+        i2c.cr1.modify(|_, w| {
+            w.gcen()
+                .bit(config.general_call)
+                .nostretch()
+                .bit(!config.clock_stretching)
+                .sbc()
+                .clear_bit()
+                .pe()
+                .set_bit()
+        });
+
+        FMPI2cSlave { i2c, pins }
+    }
+}
+
+#[cfg(any(feature = "stm32f413", feature = "stm32f423",))]
+impl<I2C, PINS> FMPI2cSlave<I2C, PINS>
+where
+    I2C: Deref<Target = fmpi2c1::RegisterBlock>,
+{
+    /// Enables the address match, receive, transmit, stop and error interrupts
+    pub fn listen(&mut self) {
+        self.i2c.cr1.modify(|_, w| {
+            w.addrie()
+                .set_bit()
+                .rxie()
+                .set_bit()
+                .txie()
+                .set_bit()
+                .stopie()
+                .set_bit()
+                .nackie()
+                .set_bit()
+                .errie()
+                .set_bit()
+        });
+    }
+
+    /// Disables the address match, receive, transmit, stop and error interrupts
+    pub fn unlisten(&mut self) {
+        self.i2c.cr1.modify(|_, w| {
+            w.addrie()
+                .clear_bit()
+                .rxie()
+                .clear_bit()
+                .txie()
+                .clear_bit()
+                .stopie()
+                .clear_bit()
+                .nackie()
+                .clear_bit()
+                .errie()
+                .clear_bit()
+        });
+    }
+
+    /// Returns the next event on the bus
+    pub fn poll(&mut self) -> nb::Result<Event, Error> {
+        let isr = self.i2c.isr.read();
+
+        if isr.berr().bit_is_set() {
+            self.i2c.icr.write(|w| w.berrcf().set_bit());
+            return Err(nb::Error::Other(Error::BUS));
+        }
+
+        if isr.ovr().bit_is_set() {
+            self.i2c.icr.write(|w| w.ovrcf().set_bit());
+            return Err(nb::Error::Other(Error::OVERRUN));
+        }
+
+        if isr.addr().bit_is_set() {
+            let code = isr.addcode().bits();
+            let address = if code == 0 && self.i2c.cr1.read().gcen().bit_is_set() {
+                MatchedAddress::GeneralCall
+            } else if code == self.i2c.oar1.read().oa11_7().bits() {
+                MatchedAddress::Primary
+            } else {
+                MatchedAddress::Secondary
+            };
+            let direction = if isr.dir().bit_is_set() {
+                // Drop any byte left over from the previous read
+                self.i2c.isr.write(|w| w.txe().set_bit());
+                Direction::Read
+            } else {
+                Direction::Write
+            };
+
+            self.i2c.icr.write(|w| w.addrcf().set_bit());
+            return Ok(Event::AddressMatch { address, direction });
+        }
+
+        if isr.rxne().bit_is_set() {
+            return Ok(Event::Received(self.i2c.rxdr.read().bits() as u8));
+        }
+
+        // The master ends a read by not acknowledging the last byte, the transfer is finished
+        // by the following STOP condition
+        if isr.nackf().bit_is_set() {
+            self.i2c.icr.write(|w| w.nackcf().set_bit());
+        }
+
+        if isr.stopf().bit_is_set() {
+            self.i2c.icr.write(|w| w.stopcf().set_bit());
+            return Ok(Event::Stop);
+        }
+
+        if isr.txis().bit_is_set() {
+            return Ok(Event::TransmitRequest);
+        }
+
+        Err(nb::Error::WouldBlock)
+    }
+
+    /// Sends a byte in response to [`Event::TransmitRequest`]
+    pub fn write(&mut self, byte: u8) {
+        self.i2c.txdr.write(|w| unsafe { w.bits(u32::from(byte)) });
+    }
+
+    pub fn release(self) -> (I2C, PINS) {
+        self.i2c.cr1.modify(|_, w| w.pe().clear_bit());
+        (self.i2c, self.pins)
+    }
+}
@@ -0,0 +1,282 @@
+//! SMBus and PMBus host
+//!
+//! [`SmBus`] runs the I2C peripheral in SMBus host mode and implements the SMBus transaction
+//! types used by battery gauges, PMBus regulators and other SMBus devices. Packet error checking
+//! is done by the hardware: the PEC byte is appended to every write and checked on every read, a
+//! mismatch is reported as [`Error::CRC`].
+//!
+//! Devices request attention by pulling the SMBALERT pin low. To receive alerts the pin has to be
+//! passed as the third element of the pins tuple, then `enable_alert` makes the peripheral
+//! monitor it. An alert can be polled with `is_alert` or raise the I2C error interrupt after
+//! `listen_alert`, `alert_response` returns the address of the device that raised it.
+//!
+//! ```ignore
+//! let i2c = I2c::new(dp.I2C1, (scl, sda, smba), 100.khz(), clocks);
+//! let mut smbus = i2c.into_smbus();
+//!
+//! // Battery voltage in mV
+//! let voltage = smbus.read_word_data(0x0b, 0x09)?;
+//! ```
+
+use super::{check_and_clear_error_flags, Error, I2c, I2cCommon, Instance};
+
+/// Address the devices with a pending alert respond to
+const ALERT_RESPONSE_ADDRESS: u8 = 0x0c;
+
+/// I2C peripheral in SMBus host mode
+pub struct SmBus<I2C: Instance, PINS> {
+    i2c: I2c<I2C, PINS>,
+    pec: bool,
+}
+
+impl<I2C, PINS> SmBus<I2C, PINS>
+where
+    I2C: Instance,
+{
+    pub(super) fn new(i2c: I2c<I2C, PINS>) -> Self {
+        i2c.i2c.cr1.modify(|_, w| w.pe().clear_bit());
+        i2c.i2c.cr1.modify(|_, w| {
+            w.smbus()
+                .set_bit()
+                .smbtype()
+                .set_bit()
+                .enpec()
+                .set_bit()
+                .pe()
+                .set_bit()
+        });
+
+        SmBus { i2c, pec: true }
+    }
+
+    /// Switches the peripheral back to plain I2C mode
+    pub fn into_i2c(self) -> I2c<I2C, PINS> {
+        self.i2c.i2c.cr1.modify(|_, w| w.pe().clear_bit());
+        self.i2c.i2c.cr1.modify(|_, w| {
+            w.smbus()
+                .clear_bit()
+                .smbtype()
+                .clear_bit()
+                .enpec()
+                .clear_bit()
+                .alert()
+                .clear_bit()
+                .pe()
+                .set_bit()
+        });
+
+        self.i2c
+    }
+
+    /// Enables or disables packet error checking, enabled by default
+    pub fn set_pec(&mut self, pec: bool) {
+        self.pec = pec;
+        self.i2c.i2c.cr1.modify(|_, w| w.enpec().bit(pec));
+    }
+
+    /// Starts monitoring the SMBALERT pin
+    pub fn enable_alert(&mut self) {
+        self.i2c.i2c.cr1.modify(|_, w| w.alert().set_bit());
+        self.clear_alert();
+    }
+
+    /// Stops monitoring the SMBALERT pin
+    pub fn disable_alert(&mut self) {
+        self.i2c.i2c.cr1.modify(|_, w| w.alert().clear_bit());
+    }
+
+    /// Enables the error interrupt, which is also raised by an alert
+    pub fn listen_alert(&mut self) {
+        self.i2c.i2c.cr2.modify(|_, w| w.iterren().set_bit());
+    }
+
+    /// Disables the error interrupt
+    pub fn unlisten_alert(&mut self) {
+        self.i2c.i2c.cr2.modify(|_, w| w.iterren().clear_bit());
+    }
+
+    /// Returns true if a device pulled SMBALERT low
+    pub fn is_alert(&self) -> bool {
+        self.i2c.i2c.sr1.read().smbalert().bit_is_set()
+    }
+
+    /// Clears the alert flag
+    pub fn clear_alert(&mut self) {
+        self.i2c.i2c.sr1.modify(|_, w| w.smbalert().clear_bit());
+    }
+
+    /// Returns the address of the device that raised an alert
+    ///
+    /// Devices release SMBALERT once their address has been read, a device with the lowest
+    /// address wins if several devices raised an alert.
+    pub fn alert_response(&mut self) -> Result<u8, Error> {
+        let mut address = [0];
+        self.i2c.start_transaction();
+        self.read(ALERT_RESPONSE_ADDRESS, &mut address)?;
+        Ok(address[0] >> 1)
+    }
+
+    /// Send Byte protocol
+    pub fn write_byte(&mut self, addr: u8, value: u8) -> Result<(), Error> {
+        self.i2c.start_transaction();
+        self.write(addr, &[value])
+    }
+
+    /// Receive Byte protocol
+    pub fn read_byte(&mut self, addr: u8) -> Result<u8, Error> {
+        let mut value = [0];
+        self.i2c.start_transaction();
+        self.read(addr, &mut value)?;
+        Ok(value[0])
+    }
+
+    /// Write Byte protocol
+    pub fn write_byte_data(&mut self, addr: u8, command: u8, value: u8) -> Result<(), Error> {
+        self.i2c.start_transaction();
+        self.write(addr, &[command, value])
+    }
+
+    /// Read Byte protocol
+    pub fn read_byte_data(&mut self, addr: u8, command: u8) -> Result<u8, Error> {
+        let mut value = [0];
+        self.i2c.start_transaction();
+        self.i2c.write_bytes(addr, &[command])?;
+        self.read(addr, &mut value)?;
+        Ok(value[0])
+    }
+
+    /// Write Word protocol
+    pub fn write_word_data(&mut self, addr: u8, command: u8, value: u16) -> Result<(), Error> {
+        let [low, high] = value.to_le_bytes();
+        self.i2c.start_transaction();
+        self.write(addr, &[command, low, high])
+    }
+
+    /// Read Word protocol
+    pub fn read_word_data(&mut self, addr: u8, command: u8) -> Result<u16, Error> {
+        let mut value = [0; 2];
+        self.i2c.start_transaction();
+        self.i2c.write_bytes(addr, &[command])?;
+        self.read(addr, &mut value)?;
+        Ok(u16::from_le_bytes(value))
+    }
+
+    /// Process Call protocol, writes `value` and reads back the response word
+    pub fn process_call(&mut self, addr: u8, command: u8, value: u16) -> Result<u16, Error> {
+        let [low, high] = value.to_le_bytes();
+        let mut response = [0; 2];
+        self.i2c.start_transaction();
+        self.i2c.write_bytes(addr, &[command, low, high])?;
+        self.read(addr, &mut response)?;
+        Ok(u16::from_le_bytes(response))
+    }
+
+    /// Block Write protocol
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` is longer than 255 bytes.
+    pub fn block_write(&mut self, addr: u8, command: u8, data: &[u8]) -> Result<(), Error> {
+        assert!(data.len() <= 255);
+
+        self.i2c.start_transaction();
+        self.i2c.write_bytes(addr, &[command, data.len() as u8])?;
+        for byte in data {
+            self.i2c.send_byte(*byte)?;
+        }
+        self.stop_write()
+    }
+
+    /// Block Read protocol, returns the number of bytes read into `buffer`
+    ///
+    /// Returns [`Error::OVERRUN`] if the device sends more bytes than fit into `buffer`.
+    pub fn block_read(&mut self, addr: u8, command: u8, buffer: &mut [u8]) -> Result<usize, Error> {
+        self.i2c.start_transaction();
+        self.i2c.write_bytes(addr, &[command])?;
+        self.i2c.start_read(addr)?;
+
+        let count = usize::from(self.i2c.recv_byte()?);
+        if count > buffer.len() || (count == 0 && !self.pec) {
+            // Abort after the next byte, the count byte has already been acknowledged
+            self.i2c
+                .i2c
+                .cr1
+                .modify(|_, w| w.ack().clear_bit().stop().set_bit());
+            self.i2c.recv_byte()?;
+            self.i2c
+                .wait(|i2c| Ok(i2c.cr1.read().stop().bit_is_clear()))?;
+            return Err(Error::OVERRUN);
+        }
+
+        self.recv(&mut buffer[..count])?;
+        Ok(count)
+    }
+
+    /// Writes `bytes`, followed by the PEC if enabled, and sends a STOP condition
+    fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Error> {
+        self.i2c.write_bytes(addr, bytes)?;
+        self.stop_write()
+    }
+
+    fn stop_write(&mut self) -> Result<(), Error> {
+        if self.pec {
+            // The PEC is transmitted once the last byte has been sent
+            self.i2c.i2c.cr1.modify(|_, w| w.pec().set_bit());
+            self.i2c.wait(|i2c| {
+                check_and_clear_error_flags(i2c)?;
+                Ok(i2c.cr1.read().pec().bit_is_clear())
+            })?;
+        }
+
+        // Send a STOP condition
+        self.i2c.i2c.cr1.modify(|_, w| w.stop().set_bit());
+
+        // Wait for STOP condition to transmit.
+        self.i2c
+            .wait(|i2c| Ok(i2c.cr1.read().stop().bit_is_clear()))
+    }
+
+    /// Reads `buffer.len()` bytes with a (repeated) START condition
+    fn read(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Error> {
+        self.i2c.start_read(addr)?;
+        self.recv(buffer)
+    }
+
+    /// Receives `buffer`, followed by the PEC if enabled, and sends a STOP condition
+    ///
+    /// `buffer` must not be empty if PEC is disabled.
+    fn recv(&mut self, buffer: &mut [u8]) -> Result<(), Error> {
+        // All bytes but the last one on the bus are acknowledged
+        let acknowledged = if self.pec {
+            buffer.len()
+        } else {
+            buffer.len() - 1
+        };
+
+        for c in &mut buffer[..acknowledged] {
+            *c = self.i2c.recv_byte()?;
+        }
+
+        // Prepare to send NACK then STOP after next byte, which is compared against the
+        // calculated PEC if enabled
+        let pec = self.pec;
+        self.i2c
+            .i2c
+            .cr1
+            .modify(|_, w| w.ack().clear_bit().stop().set_bit().pec().bit(pec));
+
+        let last = self.i2c.recv_byte()?;
+        if !pec {
+            buffer[acknowledged] = last;
+        }
+
+        // Wait for the STOP to be sent.
+        self.i2c
+            .wait(|i2c| Ok(i2c.cr1.read().stop().bit_is_clear()))?;
+
+        // A PEC mismatch is reported as CRC error
+        check_and_clear_error_flags(&self.i2c.i2c)?;
+
+        Ok(())
+    }
+}
@@ -108,6 +108,8 @@ pub mod bb;
 ))]
 pub mod can;
 #[cfg(feature = "device-selected")]
+pub mod capture;
+#[cfg(feature = "device-selected")]
 pub mod crc32;
 #[cfg(all(
     feature = "device-selected",
@@ -180,6 +182,8 @@ pub use stm32 as pac;
 pub mod dma;
 #[cfg(feature = "device-selected")]
 pub mod dwt;
+#[cfg(feature = "device-selected")]
+pub mod flash;
 #[cfg(all(
     feature = "fsmc_lcd",
     any(
@@ -200,11 +204,15 @@ pub mod dwt;
     )
 ))]
 pub mod fsmc_lcd;
+#[cfg(all(feature = "device-selected", feature = "rtic"))]
+pub mod monotonic;
 #[cfg(feature = "device-selected")]
 pub mod prelude;
 #[cfg(feature = "device-selected")]
 pub mod pwm;
 #[cfg(feature = "device-selected")]
+pub mod pwr;
+#[cfg(feature = "device-selected")]
 pub mod qei;
 #[cfg(feature = "device-selected")]
 pub mod rcc;
@@ -0,0 +1,420 @@
+//! # Monotonic timer for RTIC
+//!
+//! [`MonoTimer64`] turns one of the 32-bit general purpose timers (TIM2 or TIM5) into a
+//! free-running [`rtic::Monotonic`] clock. Counter overflows are counted in the update interrupt,
+//! which extends the count to 64 bits, so unlike [`MonoTimer`](crate::timer::MonoTimer) it
+//! doesn't wrap after a few seconds and keeps counting while the core is sleeping.
+//!
+//! The timer interrupt has to be bound to a task which calls [`MonoTimer64::on_interrupt`],
+//! otherwise the upper half of the count is lost. The same interrupt also reports the deadline
+//! armed with [`MonoTimer64::schedule`] through the capture/compare channel 1.
+//!
+//! ```ignore
+//! #[app(
+//!     device = stm32f4xx_hal::pac,
+//!     peripherals = true,
+//!     monotonic = stm32f4xx_hal::monotonic::MonoTimer64<stm32f4xx_hal::pac::TIM2>
+//! )]
+//! const APP: () = {
+//!     struct Resources {
+//!         mono: MonoTimer64<TIM2>,
+//!     }
+//!
+//!     #[init]
+//!     fn init(cx: init::Context) -> init::LateResources {
+//!         let clocks = cx.device.RCC.constrain().cfgr.sysclk(168.mhz()).freeze();
+//!         // Count at 1 MHz
+//!         let mono = MonoTimer64::tim2(cx.device.TIM2, clocks, 1.mhz());
+//!         init::LateResources { mono }
+//!     }
+//!
+//!     #[task(binds = TIM2, priority = 2, resources = [mono])]
+//!     fn tim2(cx: tim2::Context) {
+//!         cx.resources.mono.on_interrupt();
+//!     }
+//! };
+//! ```
+use core::{
+    cell::Cell,
+    convert::TryFrom,
+    num::TryFromIntError,
+    ops::{Add, AddAssign, Sub, SubAssign},
+};
+
+use cast::u16;
+use cortex_m::interrupt::{self, Mutex};
+use rtic::{Fraction, Monotonic};
+
+use crate::{bb, pac::RCC, rcc::Clocks, time::Hertz};
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::pac::TIM2;
+use crate::pac::TIM5;
+
+/// A point in time of a [`MonoTimer64`], in timer ticks
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant {
+    ticks: u64,
+}
+
+impl Instant {
+    /// Returns the number of ticks since the timer was reset
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Returns the time elapsed since `earlier`, or zero if `earlier` is later than `self`
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        Duration {
+            ticks: self.ticks.saturating_sub(earlier.ticks),
+        }
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Self;
+
+    fn add(self, rhs: Duration) -> Self {
+        Instant {
+            ticks: self.ticks + rhs.ticks,
+        }
+    }
+}
+
+impl AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, rhs: Duration) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub<Duration> for Instant {
+    type Output = Self;
+
+    fn sub(self, rhs: Duration) -> Self {
+        Instant {
+            ticks: self.ticks - rhs.ticks,
+        }
+    }
+}
+
+impl SubAssign<Duration> for Instant {
+    fn sub_assign(&mut self, rhs: Duration) {
+        *self = *self - rhs;
+    }
+}
+
+impl Sub for Instant {
+    type Output = Duration;
+
+    fn sub(self, rhs: Instant) -> Duration {
+        self.duration_since(rhs)
+    }
+}
+
+/// A span of time of a [`MonoTimer64`], in timer ticks
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Duration {
+    ticks: u64,
+}
+
+impl Duration {
+    /// Creates a duration of `ticks` timer ticks
+    pub const fn from_ticks(ticks: u64) -> Self {
+        Duration { ticks }
+    }
+
+    /// Returns the number of timer ticks in this duration
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+}
+
+impl Add for Duration {
+    type Output = Self;
+
+    fn add(self, rhs: Duration) -> Self {
+        Duration {
+            ticks: self.ticks + rhs.ticks,
+        }
+    }
+}
+
+impl Sub for Duration {
+    type Output = Self;
+
+    fn sub(self, rhs: Duration) -> Self {
+        Duration {
+            ticks: self.ticks - rhs.ticks,
+        }
+    }
+}
+
+// Required by the RTIC timer queue, durations that don't fit saturate the SysTick reload
+impl TryFrom<Duration> for u32 {
+    type Error = TryFromIntError;
+
+    fn try_from(duration: Duration) -> Result<u32, TryFromIntError> {
+        u32::try_from(duration.ticks)
+    }
+}
+
+/// State shared between the timer handle and the static `Monotonic` functions
+struct State {
+    /// Upper half of the 64-bit count
+    overflows: Cell<u32>,
+    /// Deadline armed with `MonoTimer64::schedule`
+    deadline: Cell<Option<u64>>,
+    /// `Monotonic::ratio` as (numerator, denominator)
+    ratio: Cell<(u32, u32)>,
+}
+
+impl State {
+    const fn new() -> Self {
+        State {
+            overflows: Cell::new(0),
+            deadline: Cell::new(None),
+            ratio: Cell::new((1, 1)),
+        }
+    }
+}
+
+const fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        let r = a % b;
+        a = b;
+        b = r;
+    }
+    a
+}
+
+/// Free-running 64-bit monotonic clock on a 32-bit timer
+pub struct MonoTimer64<TIM> {
+    tim: TIM,
+    frequency: Hertz,
+}
+
+macro_rules! hal {
+    ($($TIM:ident: ($tim:ident, $en_bit:expr, $reset_bit:expr, $apbenr:ident, $apbrstr:ident, $pclk:ident, $ppre:ident),)+) => {
+        $(
+            impl MonoTimer64<$TIM> {
+                /// Configures the timer as a monotonic clock counting at `freq`
+                ///
+                /// `freq` is rounded to the closest prescaler setting, see
+                /// [`frequency`](Self::frequency). The update interrupt is enabled to count
+                /// overflows.
+                pub fn $tim<T>(tim: $TIM, clocks: Clocks, freq: T) -> Self
+                where
+                    T: Into<Hertz>,
+                {
+                    unsafe {
+                        // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+                        let rcc = &(*RCC::ptr());
+                        // Enable and reset clock.
+                        bb::set(&rcc.$apbenr, $en_bit);
+                        // Stall the pipeline to work around erratum 2.1.13 (DM00037591)
+                        cortex_m::asm::dsb();
+                        bb::set(&rcc.$apbrstr, $reset_bit);
+                        bb::clear(&rcc.$apbrstr, $reset_bit);
+                    }
+
+                    let clk = clocks.$pclk().0 * if clocks.$ppre() == 1 { 1 } else { 2 };
+                    let psc = u16(clk / freq.into().0 - 1).unwrap();
+                    let freq = clk / (psc as u32 + 1);
+
+                    tim.psc.write(|w| w.psc().bits(psc));
+                    tim.arr.write(|w| unsafe { w.bits(u32::MAX) });
+
+                    // Trigger update event to load the registers
+                    tim.cr1.modify(|_, w| w.urs().set_bit());
+                    tim.egr.write(|w| w.ug().set_bit());
+                    tim.cr1.modify(|_, w| w.urs().clear_bit());
+
+                    // SysTick, which schedules the RTIC timer queue, runs from the core clock
+                    let hclk = clocks.hclk().0;
+                    let div = gcd(hclk, freq);
+                    interrupt::free(|cs| {
+                        let state = Self::state().borrow(cs);
+                        state.overflows.set(0);
+                        state.deadline.set(None);
+                        state.ratio.set((hclk / div, freq / div));
+                    });
+
+                    tim.dier.write(|w| w.uie().set_bit());
+                    tim.cr1.modify(|_, w| w.cen().set_bit());
+
+                    MonoTimer64 {
+                        tim,
+                        frequency: Hertz(freq),
+                    }
+                }
+
+                fn state() -> &'static Mutex<State> {
+                    static STATE: Mutex<State> = Mutex::new(State::new());
+                    &STATE
+                }
+
+                /// Returns the current time
+                pub fn now() -> Instant {
+                    // NOTE(unsafe) atomic reads with no side effects
+                    let tim = unsafe { &*$TIM::ptr() };
+                    interrupt::free(|cs| {
+                        let mut overflows = Self::state().borrow(cs).overflows.get();
+                        let mut cnt = tim.cnt.read().bits();
+                        // An overflow which `on_interrupt` hasn't handled yet, the counter
+                        // has to be read again as it may have wrapped after the first read
+                        if tim.sr.read().uif().bit_is_set() {
+                            overflows = overflows.wrapping_add(1);
+                            cnt = tim.cnt.read().bits();
+                        }
+                        Instant {
+                            ticks: (overflows as u64) << 32 | cnt as u64,
+                        }
+                    })
+                }
+
+                /// Returns the counting frequency of the timer
+                pub fn frequency(&self) -> Hertz {
+                    self.frequency
+                }
+
+                /// Arms the compare interrupt to fire at `instant`
+                ///
+                /// The interrupt fires right away if `instant` has already passed, and
+                /// [`on_interrupt`](Self::on_interrupt) returns `true` once it's reached.
+                /// This replaces any previously armed deadline.
+                pub fn schedule(&mut self, instant: Instant) {
+                    interrupt::free(|cs| Self::state().borrow(cs).deadline.set(Some(instant.ticks)));
+                    // The compare channel only sees the lower half of the count, matches in
+                    // earlier overflow periods are filtered out by `on_interrupt`
+                    self.tim.ccr1.write(|w| unsafe { w.bits(instant.ticks as u32) });
+                    self.tim.sr.write(|w| unsafe { w.bits(!(1 << 1)) });
+                    unsafe { bb::set(&self.tim.dier, 1) }
+
+                    // The counter may have passed the compare value while it was written
+                    if Self::now() >= instant {
+                        self.tim.egr.write(|w| w.cc1g().set_bit());
+                    }
+                }
+
+                /// Disarms the compare interrupt
+                pub fn cancel(&mut self) {
+                    unsafe { bb::clear(&self.tim.dier, 1) }
+                    self.tim.sr.write(|w| unsafe { w.bits(!(1 << 1)) });
+                    interrupt::free(|cs| Self::state().borrow(cs).deadline.set(None));
+                }
+
+                /// Handles the timer interrupt
+                ///
+                /// This must be called from the timer interrupt handler to extend the count
+                /// past 32 bits. Returns `true` if the deadline armed with
+                /// [`schedule`](Self::schedule) has been reached, which also disarms it.
+                pub fn on_interrupt(&mut self) -> bool {
+                    interrupt::free(|cs| {
+                        let state = Self::state().borrow(cs);
+                        let sr = self.tim.sr.read();
+
+                        if sr.uif().bit_is_set() {
+                            self.tim.sr.write(|w| unsafe { w.bits(!1) });
+                            state.overflows.set(state.overflows.get().wrapping_add(1));
+                        }
+
+                        if sr.cc1if().bit_is_clear() {
+                            return false;
+                        }
+                        self.tim.sr.write(|w| unsafe { w.bits(!(1 << 1)) });
+
+                        match state.deadline.get() {
+                            Some(deadline) if Self::now().ticks >= deadline => {
+                                state.deadline.set(None);
+                                unsafe { bb::clear(&self.tim.dier, 1) }
+                                true
+                            }
+                            _ => false,
+                        }
+                    })
+                }
+
+                /// Stops the timer and releases the TIM peripheral
+                pub fn release(self) -> $TIM {
+                    self.tim.cr1.modify(|_, w| w.cen().clear_bit());
+                    self.tim.dier.reset();
+                    self.tim
+                }
+            }
+
+            impl Monotonic for MonoTimer64<$TIM> {
+                type Instant = Instant;
+
+                fn ratio() -> Fraction {
+                    let (numerator, denominator) =
+                        interrupt::free(|cs| Self::state().borrow(cs).ratio.get());
+                    Fraction {
+                        numerator,
+                        denominator,
+                    }
+                }
+
+                fn now() -> Instant {
+                    Self::now()
+                }
+
+                unsafe fn reset() {
+                    let tim = &*$TIM::ptr();
+                    interrupt::free(|cs| {
+                        let state = Self::state().borrow(cs);
+                        tim.cnt.write(|w| w.bits(0));
+                        tim.sr.write(|w| w.bits(!1));
+                        state.overflows.set(0);
+                    });
+                }
+
+                fn zero() -> Instant {
+                    Instant { ticks: 0 }
+                }
+            }
+        )+
+    }
+}
+
+hal! {
+    TIM5: (tim5, 3, 3, apb1enr, apb1rstr, pclk1, ppre1),
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+hal! {
+    TIM2: (tim2, 0, 0, apb1enr, apb1rstr, pclk1, ppre1),
+}
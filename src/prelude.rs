@@ -12,6 +12,7 @@ pub use crate::dac::DacExt as _stm32f4xx_hal_dac_DacExt;
 pub use crate::gpio::ExtiPin as _stm32f4xx_hal_gpio_ExtiPin;
 pub use crate::gpio::GpioExt as _stm32f4xx_hal_gpio_GpioExt;
 pub use crate::i2c::Pins as _stm32f4xx_hal_i2c_Pins;
+pub use crate::pwr::PwrExt as _stm32f4xx_hal_pwr_PwrExt;
 pub use crate::rcc::RccExt as _stm32f4xx_hal_rcc_RccExt;
 #[cfg(all(
     feature = "device-selected",
@@ -72,6 +72,7 @@ use crate::timer::PinC1;
 use crate::timer::PinC2;
 use crate::timer::PinC3;
 use crate::timer::PinC4;
+use crate::timer::{PinBkin, PinC1N, PinC2N, PinC3N};
 
 pub struct C1;
 pub struct C2;
@@ -83,6 +84,49 @@ pub struct PwmChannels<TIM, CHANNELS> {
     _tim: PhantomData<TIM>,
 }
 
+/// Output level of a channel while the outputs are disabled by the break input or by clearing MOE
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum IdleState {
+    Low,
+    High,
+}
+
+/// Active level of an output or input
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Polarity {
+    ActiveHigh,
+    ActiveLow,
+}
+
+/// PWM output mode
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Mode {
+    /// The channel is active while the counter is below the duty
+    Pwm1,
+    /// The channel is inactive while the counter is below the duty
+    Pwm2,
+}
+
+/// Counter alignment
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Alignment {
+    /// The counter counts up, the default
+    Edge,
+    /// The counter counts up and down, compare flags are set while counting down
+    Center1,
+    /// The counter counts up and down, compare flags are set while counting up
+    Center2,
+    /// The counter counts up and down, compare flags are set in both directions
+    Center3,
+}
+
+/// Edge of the trigger input
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Edge {
+    Rising,
+    Falling,
+}
+
 macro_rules! pins_impl {
     ( $( ( $($PINX:ident),+ ), ( $($TRAIT:ident),+ ), ( $($ENCHX:ident),* ); )+ ) => {
         $(
@@ -924,3 +968,682 @@ pwm_1_channel!(
 pwm_tim5_f410!(
     TIM5: (tim5, apb1enr, apb1rstr, 3u8, pclk1, ppre1),
 );
+
+// Complementary outputs, dead-time and break input of the advanced-control timers
+//
+// The settings shared by all channels of the timer can be changed through any of its channels.
+macro_rules! complementary {
+    ($($TIMX:ident: ($pclk:ident, $ppre:ident),)+) => {
+        $(
+            impl<C> PwmChannels<$TIMX, C> {
+                /// Inserts a dead time of at least `ns` nanoseconds between a channel output and
+                /// its complementary output switching on
+                ///
+                /// Panics if the dead time is longer than 4032 cycles of the timer clock.
+                pub fn set_dead_time(&mut self, clocks: &Clocks, ns: u32) {
+                    let clk = clocks.$pclk().0 * if clocks.$ppre() == 1 { 1 } else { 2 };
+                    // round up, the dead time must not be shorter than requested
+                    let ticks = (ns as u64 * clk as u64).div_ceil(1_000_000_000) as u32;
+
+                    // the dead-time generator runs from the timer clock divided by CKD
+                    let (ckd, ticks) = match ticks {
+                        0..=1008 => (0b00, ticks),
+                        1009..=2016 => (0b01, ticks.div_ceil(2)),
+                        2017..=4032 => (0b10, ticks.div_ceil(4)),
+                        _ => panic!("dead time too long"),
+                    };
+                    let dtg = match ticks {
+                        0..=127 => ticks,
+                        128..=254 => 0b1000_0000 | (ticks.div_ceil(2) - 64),
+                        255..=504 => 0b1100_0000 | (ticks.div_ceil(8) - 32),
+                        _ => 0b1110_0000 | (ticks.div_ceil(16) - 32),
+                    };
+
+                    unsafe {
+                        let tim = &*$TIMX::ptr();
+                        tim.cr1.modify(|r, w| w.bits((r.bits() & !(0b11 << 8)) | (ckd << 8)));
+                        tim.bdtr.modify(|r, w| w.bits((r.bits() & !0xff) | dtg));
+                    }
+                }
+
+                /// Enables the break input, which disables the outputs while it is active
+                pub fn enable_break<P: PinBkin<$TIMX>>(&mut self, _pin: P, polarity: Polarity) {
+                    unsafe {
+                        let tim = &*$TIMX::ptr();
+                        // Set BDTR - Bit 13 (BKP)
+                        bb::write(&tim.bdtr, 13, polarity == Polarity::ActiveHigh);
+                        // Set BDTR - Bit 12 (BKE)
+                        bb::set(&tim.bdtr, 12);
+                    }
+                }
+
+                /// Disables the break input
+                pub fn disable_break(&mut self) {
+                    // Clear BDTR - Bit 12 (BKE)
+                    unsafe { bb::clear(&(*$TIMX::ptr()).bdtr, 12) }
+                }
+
+                /// Returns true if the break input has been active since the flag has been cleared
+                pub fn is_break_triggered(&self) -> bool {
+                    unsafe { (*$TIMX::ptr()).sr.read().bif().bit_is_set() }
+                }
+
+                /// Clears the break flag
+                pub fn clear_break(&mut self) {
+                    // Clear SR - Bit 7 (BIF), the flags are cleared by writing 0
+                    unsafe { (*$TIMX::ptr()).sr.write(|w| w.bits(!(1 << 7))) }
+                }
+
+                /// Enables the break interrupt
+                pub fn listen_break(&mut self) {
+                    // Set DIER - Bit 7 (BIE)
+                    unsafe { bb::set(&(*$TIMX::ptr()).dier, 7) }
+                }
+
+                /// Disables the break interrupt
+                pub fn unlisten_break(&mut self) {
+                    // Clear DIER - Bit 7 (BIE)
+                    unsafe { bb::clear(&(*$TIMX::ptr()).dier, 7) }
+                }
+
+                /// Enables the outputs again at the next update event once the break input is
+                /// inactive
+                ///
+                /// This is enabled by default. Otherwise the outputs stay disabled after a break
+                /// until [`enable_outputs`](Self::enable_outputs) is called.
+                pub fn set_automatic_output(&mut self, automatic: bool) {
+                    // Set BDTR - Bit 14 (AOE)
+                    unsafe { bb::write(&(*$TIMX::ptr()).bdtr, 14, automatic) }
+                }
+
+                /// Enables the outputs of all channels (sets MOE)
+                pub fn enable_outputs(&mut self) {
+                    // Set BDTR - Bit 15 (MOE)
+                    unsafe { bb::set(&(*$TIMX::ptr()).bdtr, 15) }
+                }
+
+                /// Disables the outputs of all channels, they are driven to their idle state
+                pub fn disable_outputs(&mut self) {
+                    // Clear BDTR - Bit 15 (MOE)
+                    unsafe { bb::clear(&(*$TIMX::ptr()).bdtr, 15) }
+                }
+
+                /// Keeps disabled outputs driven at their inactive level while the outputs are
+                /// enabled (OSSR) and at their idle level while they are disabled (OSSI), instead
+                /// of releasing them
+                pub fn set_off_state(&mut self, run: bool, idle: bool) {
+                    unsafe {
+                        let tim = &*$TIMX::ptr();
+                        // Set BDTR - Bit 11 (OSSR)
+                        bb::write(&tim.bdtr, 11, run);
+                        // Set BDTR - Bit 10 (OSSI)
+                        bb::write(&tim.bdtr, 10, idle);
+                    }
+                }
+            }
+
+            complementary!(@channel $TIMX, C1, PinC1N, 0);
+            complementary!(@channel $TIMX, C2, PinC2N, 1);
+            complementary!(@channel $TIMX, C3, PinC3N, 2);
+
+            impl PwmChannels<$TIMX, C4> {
+                /// Sets the output level of the channel while the outputs are disabled
+                pub fn set_idle_state(&mut self, state: IdleState) {
+                    // Set CR2 - Bit 14 (OIS4)
+                    unsafe { bb::write(&(*$TIMX::ptr()).cr2, 14, state == IdleState::High) }
+                }
+            }
+        )+
+    };
+    (@channel $TIMX:ident, $C:ident, $PINN:ident, $i:expr) => {
+        impl PwmChannels<$TIMX, $C> {
+            /// Enables the complementary output of the channel
+            pub fn enable_complementary<P: $PINN<$TIMX>>(&mut self, _pin: P) {
+                // Set CCER - Bit CCxNE
+                unsafe { bb::set(&(*$TIMX::ptr()).ccer, 2 + 4 * $i) }
+            }
+
+            /// Disables the complementary output of the channel
+            pub fn disable_complementary(&mut self) {
+                // Clear CCER - Bit CCxNE
+                unsafe { bb::clear(&(*$TIMX::ptr()).ccer, 2 + 4 * $i) }
+            }
+
+            /// Sets the active level of the complementary output
+            pub fn set_complementary_polarity(&mut self, polarity: Polarity) {
+                // Set CCER - Bit CCxNP
+                unsafe {
+                    bb::write(
+                        &(*$TIMX::ptr()).ccer,
+                        3 + 4 * $i,
+                        polarity == Polarity::ActiveLow,
+                    )
+                }
+            }
+
+            /// Sets the output level of the channel while the outputs are disabled
+            pub fn set_idle_state(&mut self, state: IdleState) {
+                // Set CR2 - Bit OISx
+                unsafe { bb::write(&(*$TIMX::ptr()).cr2, 8 + 2 * $i, state == IdleState::High) }
+            }
+
+            /// Sets the level of the complementary output while the outputs are disabled
+            ///
+            /// The dead time is still inserted, so the two outputs must not both be idle high
+            /// when driving a half-bridge.
+            pub fn set_complementary_idle_state(&mut self, state: IdleState) {
+                // Set CR2 - Bit OISxN
+                unsafe { bb::write(&(*$TIMX::ptr()).cr2, 9 + 2 * $i, state == IdleState::High) }
+            }
+        }
+    };
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+complementary!(
+    TIM1: (pclk2, ppre2),
+);
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+complementary!(
+    TIM8: (pclk2, ppre2),
+);
+
+// Output mode, polarity and preload of the channels
+macro_rules! channel_config {
+    ($($TIMX:ident: [$($C:ident: ($ccmrx_output:ident, $i:expr)),+ $(,)*],)+) => {
+        $(
+            impl<C> PwmChannels<$TIMX, C> {
+                /// Loads a new period only at the next update event, enabled by default
+                pub fn set_auto_reload_preload(&mut self, preload: bool) {
+                    // Set CR1 - Bit 7 (ARPE)
+                    unsafe { bb::write(&(*$TIMX::ptr()).cr1, 7, preload) }
+                }
+            }
+
+            $(
+                impl PwmChannels<$TIMX, $C> {
+                    /// Selects PWM mode 1 (the default) or 2
+                    pub fn set_mode(&mut self, mode: Mode) {
+                        let ocm = match mode {
+                            Mode::Pwm1 => 0b110,
+                            Mode::Pwm2 => 0b111,
+                        };
+                        let offset = 4 + 8 * ($i & 1);
+                        unsafe {
+                            (*$TIMX::ptr())
+                                .$ccmrx_output()
+                                .modify(|r, w| w.bits((r.bits() & !(0b111 << offset)) | (ocm << offset)))
+                        }
+                    }
+
+                    /// Sets the active level of the output
+                    pub fn set_polarity(&mut self, polarity: Polarity) {
+                        // Set CCER - Bit CCxP
+                        unsafe {
+                            bb::write(
+                                &(*$TIMX::ptr()).ccer,
+                                1 + 4 * $i,
+                                polarity == Polarity::ActiveLow,
+                            )
+                        }
+                    }
+
+                    /// Loads a new duty only at the next update event, enabled by default
+                    ///
+                    /// Without preload the duty is changed immediately, which may produce a glitch.
+                    pub fn set_preload(&mut self, preload: bool) {
+                        // Set CCMRx - Bit OCxPE
+                        unsafe {
+                            bb::write((*$TIMX::ptr()).$ccmrx_output(), 3 + 8 * ($i & 1), preload)
+                        }
+                    }
+                }
+            )+
+        )+
+    };
+}
+
+// Center-aligned counting of the timers that can count up and down
+macro_rules! center_aligned {
+    ($($TIMX:ident,)+) => {
+        $(
+            impl<C> PwmChannels<$TIMX, C> {
+                /// Selects edge-aligned or center-aligned counting
+                ///
+                /// In the center-aligned modes the counter counts up to the period and back down,
+                /// so the PWM frequency is half of the frequency the timer was set up with.
+                pub fn set_alignment(&mut self, alignment: Alignment) {
+                    let cms = match alignment {
+                        Alignment::Edge => 0b00,
+                        Alignment::Center1 => 0b01,
+                        Alignment::Center2 => 0b10,
+                        Alignment::Center3 => 0b11,
+                    };
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    // CMS can only be changed while the counter is disabled
+                    let cr1 = tim.cr1.read().bits();
+                    tim.cr1.write(|w| unsafe { w.bits(cr1 & !1) });
+                    tim.cr1
+                        .write(|w| unsafe { w.bits((cr1 & !(0b11 << 5) & !(1 << 4)) | (cms << 5)) });
+                }
+            }
+        )+
+    };
+}
+
+/// One-pulse mode
+///
+/// A rising or falling edge on channel 2 starts the counter, channel 1 outputs a single pulse
+/// after the configured delay. The counter stops again at the end of the pulse.
+///
+/// ```ignore
+/// // Count at 1 MHz, output a 10 us pulse 5 us after a rising edge on PA9
+/// let mut pulse = OnePulse::tim1(dp.TIM1, (pa8, pa9), clocks, 1.mhz());
+/// pulse.set_pulse(5, 10);
+/// ```
+pub struct OnePulse<TIM, PINS> {
+    tim: TIM,
+    pins: PINS,
+}
+
+macro_rules! one_pulse {
+    ($($TIMX:ident: ($timX:ident, $apbenr:ident, $apbrstr:ident, $bit:expr, $pclk:ident, $ppre:ident),)+) => {
+        $(
+            impl<P1, P2> OnePulse<$TIMX, (P1, P2)>
+            where
+                P1: PinC1<$TIMX>,
+                P2: PinC2<$TIMX>,
+            {
+                /// Configures a TIM peripheral for one-pulse mode with the counter running at
+                /// `freq`
+                ///
+                /// Channel 1 is the output, channel 2 the trigger input, triggering on rising
+                /// edges.
+                pub fn $timX<T>(tim: $TIMX, pins: (P1, P2), clocks: Clocks, freq: T) -> Self
+                where
+                    T: Into<Hertz>,
+                {
+                    unsafe {
+                        //NOTE(unsafe) this reference will only be used for atomic writes with no side effects
+                        let rcc = &(*RCC::ptr());
+                        // Enable and reset the timer peripheral, it's the same bit position for both registers
+                        bb::set(&rcc.$apbenr, $bit);
+
+                        // Stall the pipeline to work around erratum 2.1.13 (DM00037591)
+                        cortex_m::asm::dsb();
+
+                        bb::set(&rcc.$apbrstr, $bit);
+                        bb::clear(&rcc.$apbrstr, $bit);
+                    }
+
+                    let clk = clocks.$pclk().0 * if clocks.$ppre() == 1 { 1 } else { 2 };
+                    let psc = u16(clk / freq.into().0 - 1).unwrap();
+                    tim.psc.write(|w| w.psc().bits(psc));
+
+                    unsafe {
+                        // OC1M = 111 (PWM mode 2), OC1PE = 1, the output is active from the delay
+                        // until the end of the period
+                        // CC2S = 01, IC2 is mapped on TI2
+                        tim.ccmr1_output()
+                            .write(|w| w.bits(0b111 << 4 | 1 << 3 | 0b01 << 8));
+                        // enable the output and the trigger input
+                        tim.ccer.write(|w| w.bits(1 | 1 << 4));
+                        // TS = 110, trigger on TI2FP2
+                        // SMS = 110, start the counter on the trigger
+                        tim.smcr.write(|w| w.bits(0b110 << 4 | 0b110));
+                    }
+
+                    // Trigger update event to load the registers
+                    tim.cr1.modify(|_, w| w.urs().set_bit());
+                    tim.egr.write(|w| w.ug().set_bit());
+                    tim.cr1.modify(|_, w| w.urs().clear_bit());
+
+                    // stop the counter at the next update event
+                    tim.cr1.modify(|_, w| w.arpe().set_bit().opm().set_bit());
+                    moe!($TIMX, tim);
+
+                    OnePulse { tim, pins }
+                }
+
+                /// Sets the delay from the trigger to the start of the pulse and the pulse width,
+                /// both in counter ticks
+                ///
+                /// Panics if the width is 0 or the delay and width together exceed 65536 ticks.
+                pub fn set_pulse(&mut self, delay: u16, width: u16) {
+                    assert!(width > 0);
+                    let arr = u16(delay as u32 + width as u32 - 1).unwrap();
+                    unsafe {
+                        self.tim.ccr1.write(|w| w.bits(delay as u32));
+                        self.tim.arr.write(|w| w.bits(arr as u32));
+                    }
+                    // Load the registers while the counter is stopped
+                    if !self.is_busy() {
+                        self.tim.cr1.modify(|_, w| w.urs().set_bit());
+                        self.tim.egr.write(|w| w.ug().set_bit());
+                        self.tim.cr1.modify(|_, w| w.urs().clear_bit());
+                    }
+                }
+
+                /// Sets the edge of the trigger input that starts a pulse
+                pub fn set_trigger_edge(&mut self, edge: Edge) {
+                    // Set CCER - Bit 5 (CC2P)
+                    unsafe { bb::write(&self.tim.ccer, 5, edge == Edge::Falling) }
+                }
+
+                /// Sets the active level of the output
+                pub fn set_polarity(&mut self, polarity: Polarity) {
+                    // Set CCER - Bit 1 (CC1P)
+                    unsafe { bb::write(&self.tim.ccer, 1, polarity == Polarity::ActiveLow) }
+                }
+
+                /// Starts a pulse without waiting for the trigger input
+                pub fn trigger(&mut self) {
+                    self.tim.cr1.modify(|_, w| w.cen().set_bit());
+                }
+
+                /// Returns true while a pulse is being generated
+                pub fn is_busy(&self) -> bool {
+                    self.tim.cr1.read().cen().bit_is_set()
+                }
+
+                /// Releases the TIM peripheral and pins
+                pub fn release(self) -> ($TIMX, (P1, P2)) {
+                    // pause counter
+                    self.tim.cr1.modify(|_, w| w.cen().clear_bit());
+                    (self.tim, self.pins)
+                }
+            }
+        )+
+    };
+}
+
+macro_rules! moe {
+    (TIM1, $tim:ident) => {
+        $tim.bdtr.modify(|_, w| w.moe().set_bit());
+    };
+    (TIM8, $tim:ident) => {
+        $tim.bdtr.modify(|_, w| w.moe().set_bit());
+    };
+    ($_other:ident, $_tim:ident) => {};
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+channel_config!(
+    TIM1: [
+        C1: (ccmr1_output, 0),
+        C2: (ccmr1_output, 1),
+        C3: (ccmr2_output, 2),
+        C4: (ccmr2_output, 3),
+    ],
+    TIM5: [
+        C1: (ccmr1_output, 0),
+        C2: (ccmr1_output, 1),
+        C3: (ccmr2_output, 2),
+        C4: (ccmr2_output, 3),
+    ],
+    TIM9: [
+        C1: (ccmr1_output, 0),
+        C2: (ccmr1_output, 1),
+    ],
+    TIM11: [
+        C1: (ccmr1_output, 0),
+    ],
+);
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+channel_config!(
+    TIM2: [
+        C1: (ccmr1_output, 0),
+        C2: (ccmr1_output, 1),
+        C3: (ccmr2_output, 2),
+        C4: (ccmr2_output, 3),
+    ],
+    TIM3: [
+        C1: (ccmr1_output, 0),
+        C2: (ccmr1_output, 1),
+        C3: (ccmr2_output, 2),
+        C4: (ccmr2_output, 3),
+    ],
+    TIM4: [
+        C1: (ccmr1_output, 0),
+        C2: (ccmr1_output, 1),
+        C3: (ccmr2_output, 2),
+        C4: (ccmr2_output, 3),
+    ],
+    TIM10: [
+        C1: (ccmr1_output, 0),
+    ],
+);
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+channel_config!(
+    TIM8: [
+        C1: (ccmr1_output, 0),
+        C2: (ccmr1_output, 1),
+        C3: (ccmr2_output, 2),
+        C4: (ccmr2_output, 3),
+    ],
+    TIM12: [
+        C1: (ccmr1_output, 0),
+        C2: (ccmr1_output, 1),
+    ],
+    TIM13: [
+        C1: (ccmr1_output, 0),
+    ],
+    TIM14: [
+        C1: (ccmr1_output, 0),
+    ],
+);
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+center_aligned!(TIM1, TIM5,);
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+center_aligned!(TIM2, TIM3, TIM4,);
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+center_aligned!(TIM8,);
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+one_pulse!(
+    TIM1: (tim1, apb2enr, apb2rstr, 0u8, pclk2, ppre2),
+    TIM5: (tim5, apb1enr, apb1rstr, 3u8, pclk1, ppre1),
+    TIM9: (tim9, apb2enr, apb2rstr, 16u8, pclk2, ppre2),
+);
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+one_pulse!(
+    TIM2: (tim2, apb1enr, apb1rstr, 0u8, pclk1, ppre1),
+    TIM3: (tim3, apb1enr, apb1rstr, 1u8, pclk1, ppre1),
+    TIM4: (tim4, apb1enr, apb1rstr, 2u8, pclk1, ppre1),
+);
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+one_pulse!(
+    TIM8: (tim8, apb2enr, apb2rstr, 1u8, pclk2, ppre2),
+    TIM12: (tim12, apb1enr, apb1rstr, 6u8, pclk1, ppre1),
+);
@@ -0,0 +1,424 @@
+//! Power control
+//!
+//! The low-power modes are entered through [`PwrExt`], which is implemented for the `PWR`
+//! peripheral:
+//!
+//! - Sleep stops the CPU clock only, any interrupt (or event) wakes it up again.
+//! - Stop additionally stops all clocks in the 1.2 V domain, the SRAM and register contents are
+//!   kept. The MCU is woken up by an EXTI line, e.g. a GPIO interrupt or the RTC.
+//! - Standby switches off the 1.2 V domain, only the backup domain is kept. The MCU is woken up
+//!   by the WKUP pin, the RTC or NRST and restarts from reset.
+//!
+//! The programmable voltage detector [`Pvd`] compares VDD to a threshold and signals a supply
+//! drop through EXTI line 16, early enough to save data before a brown-out reset.
+//!
+//! ```ignore
+//! let mut pwr = dp.PWR;
+//! let mut scb = cp.SCB;
+//!
+//! // Wait for a button connected to an EXTI line
+//! pwr.enter_stop(&mut scb, StopMode::default().low_power_regulator(true), WaitFor::Interrupt);
+//! ```
+
+use cortex_m::{asm, peripheral::SCB};
+
+use crate::bb;
+use crate::gpio::Edge;
+use crate::pac::{EXTI, PWR, RCC};
+
+/// Instruction used to enter a low-power mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WaitFor {
+    /// Wait For Interrupt, the MCU wakes up on any enabled interrupt
+    Interrupt,
+    /// Wait For Event, the MCU wakes up on an event, e.g. an EXTI line configured as event
+    Event,
+}
+
+impl WaitFor {
+    fn wait(self) {
+        match self {
+            WaitFor::Interrupt => asm::wfi(),
+            WaitFor::Event => asm::wfe(),
+        }
+    }
+}
+
+/// Stop mode configuration
+///
+/// The default keeps the main regulator and the flash powered, which gives the shortest wakeup
+/// time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StopMode {
+    pub(crate) low_power_regulator: bool,
+    pub(crate) flash_power_down: bool,
+}
+
+impl StopMode {
+    /// Uses the low-power regulator during Stop mode, which reduces the consumption but lengthens
+    /// the wakeup time
+    pub fn low_power_regulator(mut self, low_power_regulator: bool) -> Self {
+        self.low_power_regulator = low_power_regulator;
+        self
+    }
+
+    /// Powers down the flash during Stop mode, which reduces the consumption but lengthens the
+    /// wakeup time
+    pub fn flash_power_down(mut self, flash_power_down: bool) -> Self {
+        self.flash_power_down = flash_power_down;
+        self
+    }
+}
+
+/// Extension trait for the low-power modes
+pub trait PwrExt {
+    /// Enters Sleep mode
+    fn enter_sleep(&mut self, scb: &mut SCB, wait: WaitFor);
+
+    /// Enters Sleep mode whenever the last interrupt handler returns, for applications running
+    /// entirely in interrupts
+    fn set_sleep_on_exit(&mut self, scb: &mut SCB, sleep_on_exit: bool);
+
+    /// Enters Stop mode and returns after wakeup
+    ///
+    /// The MCU wakes up running from the HSI, before returning the HSE and PLLs are started again
+    /// and the system clock is switched back, so the `Clocks` from `freeze` stay valid.
+    fn enter_stop(&mut self, scb: &mut SCB, mode: StopMode, wait: WaitFor);
+
+    /// Enters Standby mode, the MCU restarts from reset on wakeup
+    fn enter_standby(&mut self, scb: &mut SCB) -> !;
+
+    /// Enables waking up from Standby by a rising edge on the WKUP pin (PA0)
+    fn enable_wakeup_pin(&mut self, enable: bool);
+
+    /// Returns true if the MCU has been woken up from Standby
+    fn is_standby_wakeup(&self) -> bool;
+
+    /// Clears the Standby flag
+    fn clear_standby_flag(&mut self);
+}
+
+impl PwrExt for PWR {
+    fn enter_sleep(&mut self, scb: &mut SCB, wait: WaitFor) {
+        scb.clear_sleepdeep();
+        wait.wait();
+    }
+
+    fn set_sleep_on_exit(&mut self, scb: &mut SCB, sleep_on_exit: bool) {
+        if sleep_on_exit {
+            scb.set_sleeponexit();
+        } else {
+            scb.clear_sleeponexit();
+        }
+    }
+
+    fn enter_stop(&mut self, scb: &mut SCB, mode: StopMode, wait: WaitFor) {
+        enable_clock();
+
+        let rcc = unsafe { &*RCC::ptr() };
+        let clocks = SavedClocks::save(rcc);
+
+        self.cr.modify(|_, w| {
+            w.pdds()
+                .clear_bit()
+                .lpds()
+                .bit(mode.low_power_regulator)
+                .fpds()
+                .bit(mode.flash_power_down)
+                .cwuf()
+                .set_bit()
+        });
+
+        scb.set_sleepdeep();
+        wait.wait();
+        scb.clear_sleepdeep();
+
+        clocks.restore(rcc);
+    }
+
+    fn enter_standby(&mut self, scb: &mut SCB) -> ! {
+        enable_clock();
+
+        // The wakeup flag has to be cleared, otherwise the MCU wakes up immediately
+        self.cr
+            .modify(|_, w| w.pdds().set_bit().cwuf().set_bit().csbf().set_bit());
+
+        scb.set_sleepdeep();
+        loop {
+            asm::wfi();
+        }
+    }
+
+    fn enable_wakeup_pin(&mut self, enable: bool) {
+        enable_clock();
+
+        #[cfg(not(feature = "stm32f410"))]
+        self.csr.modify(|_, w| w.ewup().bit(enable));
+        #[cfg(feature = "stm32f410")]
+        self.csr.modify(|_, w| w.ewup1().bit(enable));
+    }
+
+    fn is_standby_wakeup(&self) -> bool {
+        enable_clock();
+        self.csr.read().sbf().bit_is_set()
+    }
+
+    fn clear_standby_flag(&mut self) {
+        enable_clock();
+        self.cr.modify(|_, w| w.csbf().set_bit());
+    }
+}
+
+/// PVD threshold level
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PvdLevel {
+    /// 2.2 V
+    V2_2,
+    /// 2.3 V
+    V2_3,
+    /// 2.4 V
+    V2_4,
+    /// 2.5 V
+    V2_5,
+    /// 2.6 V
+    V2_6,
+    /// 2.7 V
+    V2_7,
+    /// 2.8 V
+    V2_8,
+    /// 2.9 V
+    V2_9,
+}
+
+impl PvdLevel {
+    fn bits(self) -> u8 {
+        match self {
+            PvdLevel::V2_2 => 0b000,
+            PvdLevel::V2_3 => 0b001,
+            PvdLevel::V2_4 => 0b010,
+            PvdLevel::V2_5 => 0b011,
+            PvdLevel::V2_6 => 0b100,
+            PvdLevel::V2_7 => 0b101,
+            PvdLevel::V2_8 => 0b110,
+            PvdLevel::V2_9 => 0b111,
+        }
+    }
+}
+
+/// EXTI line the PVD output is connected to
+const PVD_EXTI_LINE: u8 = 16;
+
+/// Programmable voltage detector
+///
+/// The PVD output is set while VDD is below the threshold. A rising edge on EXTI line 16 therefore
+/// signals a falling supply and a falling edge signals the supply recovering. The interrupt is
+/// `PVD`.
+///
+/// ```ignore
+/// let mut pvd = Pvd::new(&mut dp.PWR, PvdLevel::V2_9);
+/// pvd.trigger_on_edge(&mut dp.EXTI, Edge::RISING);
+/// pvd.enable_interrupt(&mut dp.EXTI);
+/// ```
+pub struct Pvd {
+    _private: (),
+}
+
+impl Pvd {
+    /// Enables the PVD with the given threshold
+    pub fn new(pwr: &mut PWR, level: PvdLevel) -> Self {
+        enable_clock();
+
+        // NOTE(unsafe) all values are valid thresholds
+        pwr.cr
+            .modify(|_, w| unsafe { w.pls().bits(level.bits()).pvde().set_bit() });
+
+        Pvd { _private: () }
+    }
+
+    /// Changes the threshold
+    pub fn set_level(&mut self, level: PvdLevel) {
+        // NOTE(unsafe) only the PLS field is modified and all values are valid thresholds
+        unsafe { (*PWR::ptr()).cr.modify(|_, w| w.pls().bits(level.bits())) };
+    }
+
+    /// Returns true if VDD is below the threshold
+    pub fn is_below_threshold(&self) -> bool {
+        unsafe { (*PWR::ptr()).csr.read().pvdo().bit_is_set() }
+    }
+
+    /// Generate interrupt on rising edge, falling edge or both
+    pub fn trigger_on_edge(&mut self, exti: &mut EXTI, edge: Edge) {
+        let line = 1 << PVD_EXTI_LINE;
+        match edge {
+            Edge::RISING => {
+                exti.rtsr.modify(|r, w| unsafe { w.bits(r.bits() | line) });
+                exti.ftsr.modify(|r, w| unsafe { w.bits(r.bits() & !line) });
+            }
+            Edge::FALLING => {
+                exti.ftsr.modify(|r, w| unsafe { w.bits(r.bits() | line) });
+                exti.rtsr.modify(|r, w| unsafe { w.bits(r.bits() & !line) });
+            }
+            Edge::RISING_FALLING => {
+                exti.rtsr.modify(|r, w| unsafe { w.bits(r.bits() | line) });
+                exti.ftsr.modify(|r, w| unsafe { w.bits(r.bits() | line) });
+            }
+        }
+    }
+
+    /// Enable external interrupts from the PVD
+    pub fn enable_interrupt(&mut self, exti: &mut EXTI) {
+        exti.imr
+            .modify(|r, w| unsafe { w.bits(r.bits() | (1 << PVD_EXTI_LINE)) });
+    }
+
+    /// Disable external interrupts from the PVD
+    pub fn disable_interrupt(&mut self, exti: &mut EXTI) {
+        exti.imr
+            .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << PVD_EXTI_LINE)) });
+    }
+
+    /// Clear the interrupt pending bit for the PVD
+    pub fn clear_interrupt_pending_bit(&mut self) {
+        unsafe { (*EXTI::ptr()).pr.write(|w| w.bits(1 << PVD_EXTI_LINE)) };
+    }
+
+    /// Reads the interrupt pending bit for the PVD
+    pub fn check_interrupt(&self) -> bool {
+        unsafe { ((*EXTI::ptr()).pr.read().bits() & (1 << PVD_EXTI_LINE)) != 0 }
+    }
+
+    /// Disables the PVD
+    pub fn disable(self, pwr: &mut PWR) {
+        pwr.cr.modify(|_, w| w.pvde().clear_bit());
+    }
+}
+
+fn enable_clock() {
+    unsafe {
+        // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+        let rcc = &(*RCC::ptr());
+
+        // Set APB1 - Bit 28 (PWREN)
+        bb::set(&rcc.apb1enr, 28);
+    }
+
+    // Stall the pipeline to work around erratum 2.1.13 (DM00037591)
+    cortex_m::asm::dsb();
+}
+
+/// Clock state that is lost in Stop mode
+struct SavedClocks {
+    hse: bool,
+    pll: bool,
+    #[cfg(not(feature = "stm32f410"))]
+    plli2s: bool,
+    #[cfg(any(
+        feature = "stm32f427",
+        feature = "stm32f429",
+        feature = "stm32f437",
+        feature = "stm32f439",
+        feature = "stm32f446",
+        feature = "stm32f469",
+        feature = "stm32f479"
+    ))]
+    pllsai: bool,
+    #[cfg(any(
+        feature = "stm32f427",
+        feature = "stm32f429",
+        feature = "stm32f437",
+        feature = "stm32f439",
+        feature = "stm32f446",
+        feature = "stm32f469",
+        feature = "stm32f479"
+    ))]
+    overdrive: bool,
+    sw: u8,
+}
+
+impl SavedClocks {
+    fn save(rcc: &crate::pac::rcc::RegisterBlock) -> Self {
+        let cr = rcc.cr.read();
+        SavedClocks {
+            hse: cr.hseon().bit_is_set(),
+            pll: cr.pllon().bit_is_set(),
+            #[cfg(not(feature = "stm32f410"))]
+            plli2s: cr.plli2son().bit_is_set(),
+            #[cfg(any(
+                feature = "stm32f427",
+                feature = "stm32f429",
+                feature = "stm32f437",
+                feature = "stm32f439",
+                feature = "stm32f446",
+                feature = "stm32f469",
+                feature = "stm32f479"
+            ))]
+            pllsai: cr.pllsaion().bit_is_set(),
+            #[cfg(any(
+                feature = "stm32f427",
+                feature = "stm32f429",
+                feature = "stm32f437",
+                feature = "stm32f439",
+                feature = "stm32f446",
+                feature = "stm32f469",
+                feature = "stm32f479"
+            ))]
+            overdrive: unsafe { (*PWR::ptr()).cr.read().odswen().bit_is_set() },
+            sw: rcc.cfgr.read().sw().bits(),
+        }
+    }
+
+    /// Starts the oscillators and PLLs again, their configuration has been kept
+    fn restore(&self, rcc: &crate::pac::rcc::RegisterBlock) {
+        if self.hse {
+            rcc.cr.modify(|_, w| w.hseon().set_bit());
+            while rcc.cr.read().hserdy().bit_is_clear() {}
+        }
+
+        if self.pll {
+            rcc.cr.modify(|_, w| w.pllon().set_bit());
+
+            #[cfg(any(
+                feature = "stm32f427",
+                feature = "stm32f429",
+                feature = "stm32f437",
+                feature = "stm32f439",
+                feature = "stm32f446",
+                feature = "stm32f469",
+                feature = "stm32f479"
+            ))]
+            if self.overdrive {
+                let pwr = unsafe { &*PWR::ptr() };
+                pwr.cr.modify(|_, w| w.oden().set_bit());
+                while pwr.csr.read().odrdy().bit_is_clear() {}
+                pwr.cr.modify(|_, w| w.odswen().set_bit());
+                while pwr.csr.read().odswrdy().bit_is_clear() {}
+            }
+
+            while rcc.cr.read().pllrdy().bit_is_clear() {}
+        }
+
+        #[cfg(not(feature = "stm32f410"))]
+        if self.plli2s {
+            rcc.cr.modify(|_, w| w.plli2son().set_bit());
+            while rcc.cr.read().plli2srdy().bit_is_clear() {}
+        }
+
+        #[cfg(any(
+            feature = "stm32f427",
+            feature = "stm32f429",
+            feature = "stm32f437",
+            feature = "stm32f439",
+            feature = "stm32f446",
+            feature = "stm32f469",
+            feature = "stm32f479"
+        ))]
+        if self.pllsai {
+            rcc.cr.modify(|_, w| w.pllsaion().set_bit());
+            while rcc.cr.read().pllsairdy().bit_is_clear() {}
+        }
+
+        // NOTE(unsafe) the value has been read from the register before
+        rcc.cfgr.modify(|_, w| unsafe { w.sw().bits(self.sw) });
+        while rcc.cfgr.read().sws().bits() != self.sw {}
+    }
+}
@@ -68,7 +68,10 @@ pub trait RccExt {
 
 impl RccExt for RCC {
     fn constrain(self) -> Rcc {
+        let reset_reason = ResetReason::read_and_clear(&self);
+
         Rcc {
+            reset_reason,
             cfgr: CFGR {
                 hse: None,
                 hse_bypass: false,
@@ -140,6 +143,66 @@ impl RccExt for RCC {
 /// Constrained RCC peripheral
 pub struct Rcc {
     pub cfgr: CFGR,
+    reset_reason: ResetReason,
+}
+
+impl Rcc {
+    /// Returns the cause of the last reset
+    pub fn reset_reason(&self) -> ResetReason {
+        self.reset_reason
+    }
+}
+
+/// Cause of the last reset
+///
+/// A reset sets several flags in RCC_CSR, e.g. a power-on reset also sets the brownout and pin
+/// flags, the most specific one is reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResetReason {
+    /// Entering Standby or Stop mode was configured to reset instead
+    LowPower,
+    /// Window watchdog timeout or refresh outside the window
+    WindowWatchdog,
+    /// Independent watchdog timeout
+    IndependentWatchdog,
+    /// Software reset through `SCB::sys_reset`
+    Software,
+    /// Power-on or power-down reset
+    PowerOn,
+    /// Supply voltage dropped below the brownout threshold
+    Brownout,
+    /// Low level on the NRST pin
+    Pin,
+    /// No reset flag was set, e.g. because they were cleared before
+    Unknown,
+}
+
+impl ResetReason {
+    /// Reads the reset flags and clears them, so the next reset is reported correctly
+    fn read_and_clear(rcc: &RCC) -> Self {
+        let csr = rcc.csr.read();
+        let reason = if csr.lpwrrstf().bit_is_set() {
+            ResetReason::LowPower
+        } else if csr.wwdgrstf().bit_is_set() {
+            ResetReason::WindowWatchdog
+        } else if csr.wdgrstf().bit_is_set() {
+            ResetReason::IndependentWatchdog
+        } else if csr.sftrstf().bit_is_set() {
+            ResetReason::Software
+        } else if csr.porrstf().bit_is_set() {
+            ResetReason::PowerOn
+        } else if csr.borrstf().bit_is_set() {
+            ResetReason::Brownout
+        } else if csr.padrstf().bit_is_set() {
+            ResetReason::Pin
+        } else {
+            ResetReason::Unknown
+        };
+
+        rcc.csr.modify(|_, w| w.rmvf().set_bit());
+
+        reason
+    }
 }
 
 /// Built-in high speed clock frequency
@@ -3,9 +3,10 @@
 //! [ST AN4759](https:/www.st.com%2Fresource%2Fen%2Fapplication_note%2Fdm00226326-using-the-hardware-realtime-clock-rtc-and-the-tamper-management-unit-tamp-with-stm32-microcontrollers-stmicroelectronics.pdf&usg=AOvVaw3PzvL2TfYtwS32fw-Uv37h)
 
 use crate::bb;
-use crate::pac::{PWR, RTC};
+use crate::pac::{EXTI, PWR, RTC};
 use crate::stm32::rcc::RegisterBlock;
 use crate::stm32::RCC;
+use crate::time::MilliSeconds;
 use core::convert::TryInto;
 use rtcc::{Datelike, Hours, NaiveDate, NaiveDateTime, NaiveTime, Rtcc, Timelike};
 
@@ -17,6 +18,226 @@ pub enum Error {
 
 pub const LSE_BITS: u8 = 0b01;
 
+/// Frequency of the LSE, the RTC clock
+const LSE_HZ: u32 = 32_768;
+
+/// RTC alarm
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Alarm {
+    A,
+    B,
+}
+
+/// RTC interrupt events
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Event {
+    /// Alarm A matched, signalled on EXTI line 17
+    AlarmA,
+    /// Alarm B matched, signalled on EXTI line 17
+    AlarmB,
+    /// Wakeup timer elapsed, signalled on EXTI line 22
+    Wakeup,
+}
+
+impl Event {
+    fn exti_line(self) -> u8 {
+        match self {
+            Event::AlarmA | Event::AlarmB => 17,
+            Event::Wakeup => 22,
+        }
+    }
+}
+
+/// Clock of the wakeup timer
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum WakeupClock {
+    /// RTC clock divided by 16
+    RtcDiv16,
+    /// RTC clock divided by 8
+    RtcDiv8,
+    /// RTC clock divided by 4
+    RtcDiv4,
+    /// RTC clock divided by 2
+    RtcDiv2,
+    /// Calendar clock `ck_spre`, usually 1 Hz
+    CkSpre,
+    /// Calendar clock `ck_spre`, with 2^16 added to the reload value
+    CkSpreExtended,
+}
+
+impl WakeupClock {
+    fn bits(self) -> u8 {
+        match self {
+            WakeupClock::RtcDiv16 => 0b000,
+            WakeupClock::RtcDiv8 => 0b001,
+            WakeupClock::RtcDiv4 => 0b010,
+            WakeupClock::RtcDiv2 => 0b011,
+            WakeupClock::CkSpre => 0b100,
+            WakeupClock::CkSpreExtended => 0b110,
+        }
+    }
+}
+
+/// Day an alarm matches on
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum AlarmDay {
+    /// Day of the month, 1-31
+    Date(u8),
+    /// Day of the week, 1 (Monday) - 7 (Sunday)
+    Weekday(u8),
+}
+
+/// Alarm configuration
+///
+/// Every field that is not set is masked, i.e. matches any value. The default configuration
+/// therefore triggers every second.
+///
+/// ```ignore
+/// // Every day at 06:30:00
+/// let alarm = AlarmConfig::new().hours(Hours::H24(6)).minutes(30).seconds(0);
+/// rtc.set_alarm(Alarm::A, alarm)?;
+/// ```
+#[derive(Debug, Default, Copy, Clone)]
+pub struct AlarmConfig {
+    day: Option<AlarmDay>,
+    hours: Option<Hours>,
+    minutes: Option<u8>,
+    seconds: Option<u8>,
+    subseconds: u16,
+    subsecond_bits: u8,
+}
+
+impl AlarmConfig {
+    /// Creates a configuration with all fields masked
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Matches the day of the month, 1-31
+    pub fn date(mut self, date: u8) -> Self {
+        self.day = Some(AlarmDay::Date(date));
+        self
+    }
+
+    /// Matches the day of the week, 1 (Monday) - 7 (Sunday)
+    pub fn weekday(mut self, weekday: u8) -> Self {
+        self.day = Some(AlarmDay::Weekday(weekday));
+        self
+    }
+
+    /// Matches the hours, converted to the hour format the calendar is set to
+    pub fn hours(mut self, hours: Hours) -> Self {
+        self.hours = Some(hours);
+        self
+    }
+
+    /// Matches the minutes
+    pub fn minutes(mut self, minutes: u8) -> Self {
+        self.minutes = Some(minutes);
+        self
+    }
+
+    /// Matches the seconds
+    pub fn seconds(mut self, seconds: u8) -> Self {
+        self.seconds = Some(seconds);
+        self
+    }
+
+    /// Matches the `bits` least significant bits of the sub-second counter against `subseconds`
+    ///
+    /// The sub-second counter counts down from `prediv_s`. With `bits` set to 0 (the default) the
+    /// sub-seconds are not compared, up to 15 bits can be compared.
+    pub fn subseconds(mut self, subseconds: u16, bits: u8) -> Self {
+        self.subseconds = subseconds;
+        self.subsecond_bits = bits;
+        self
+    }
+
+    /// Returns the values of the `ALRMxR` and `ALRMxSSR` registers
+    ///
+    /// `h12` selects the 12 hour format, which needs the PM bit to be set for afternoon hours.
+    fn to_registers(self, h12: bool) -> Result<(u32, u32), Error> {
+        let mut alrmr = 0;
+
+        match self.day {
+            Some(AlarmDay::Date(date)) => {
+                if !(1..=31).contains(&date) {
+                    return Err(Error::InvalidInputData);
+                }
+                let (dt, du) = bcd2_encode(date as u32)?;
+                alrmr |= (dt as u32) << 28 | (du as u32) << 24;
+            }
+            Some(AlarmDay::Weekday(weekday)) => {
+                if !(1..=7).contains(&weekday) {
+                    return Err(Error::InvalidInputData);
+                }
+                // WDSEL
+                alrmr |= 1 << 30 | (weekday as u32) << 24;
+            }
+            // MSK4
+            None => alrmr |= 1 << 31,
+        }
+
+        match self.hours {
+            Some(hours) => {
+                let h24 = match hours {
+                    Hours::H24(h) if h <= 23 => h,
+                    Hours::AM(h) if (1..=12).contains(&h) => h % 12,
+                    Hours::PM(h) if (1..=12).contains(&h) => h % 12 + 12,
+                    _ => return Err(Error::InvalidInputData),
+                };
+                let h = if h12 {
+                    if h24 >= 12 {
+                        // PM
+                        alrmr |= 1 << 22;
+                    }
+                    match h24 % 12 {
+                        0 => 12,
+                        h => h,
+                    }
+                } else {
+                    h24
+                };
+                let (ht, hu) = bcd2_encode(h as u32)?;
+                alrmr |= (ht as u32) << 20 | (hu as u32) << 16;
+            }
+            // MSK3
+            None => alrmr |= 1 << 23,
+        }
+
+        match self.minutes {
+            Some(minutes) => {
+                if minutes > 59 {
+                    return Err(Error::InvalidInputData);
+                }
+                let (mnt, mnu) = bcd2_encode(minutes as u32)?;
+                alrmr |= (mnt as u32) << 12 | (mnu as u32) << 8;
+            }
+            // MSK2
+            None => alrmr |= 1 << 15,
+        }
+
+        match self.seconds {
+            Some(seconds) => {
+                if seconds > 59 {
+                    return Err(Error::InvalidInputData);
+                }
+                let (st, su) = bcd2_encode(seconds as u32)?;
+                alrmr |= (st as u32) << 4 | su as u32;
+            }
+            // MSK1
+            None => alrmr |= 1 << 7,
+        }
+
+        if (self.subseconds > 0x7FFF) || (self.subsecond_bits > 15) {
+            return Err(Error::InvalidInputData);
+        }
+        let alrmssr = (self.subsecond_bits as u32) << 24 | self.subseconds as u32;
+
+        Ok((alrmr, alrmssr))
+    }
+}
+
 pub struct Rtc {
     pub regs: RTC,
 }
@@ -80,6 +301,190 @@ impl Rtc {
         !self.regs.cr.read().fmt().bit()
     }
 
+    /// Reads backup register `n`
+    ///
+    /// Panics if `n` is not below [`BACKUP_REGISTERS`].
+    pub fn read_backup_register(&self, n: usize) -> u32 {
+        self.regs.bkpr[n].read().bkp().bits()
+    }
+
+    /// Writes backup register `n`, the value is kept across resets and in VBAT mode
+    ///
+    /// Panics if `n` is not below [`BACKUP_REGISTERS`].
+    pub fn write_backup_register(&mut self, n: usize, value: u32) {
+        self.regs.bkpr[n].write(|w| w.bkp().bits(value));
+    }
+
+    /// Configures and enables an alarm
+    ///
+    /// The hours are converted to the hour format the calendar is set to, so the alarm has to be
+    /// set again after changing the format. Returns `InvalidInputData` for hours out of range.
+    pub fn set_alarm(&mut self, alarm: Alarm, config: AlarmConfig) -> Result<(), Error> {
+        let (alrmr, alrmssr) = config.to_registers(!self.is_24h_fmt())?;
+
+        self.unprotected(|regs| match alarm {
+            Alarm::A => {
+                regs.cr.modify(|_, w| w.alrae().clear_bit());
+                while regs.isr.read().alrawf().bit_is_clear() {}
+                regs.alrmar.write(|w| unsafe { w.bits(alrmr) });
+                regs.alrmassr.write(|w| unsafe { w.bits(alrmssr) });
+                regs.cr.modify(|_, w| w.alrae().set_bit());
+            }
+            Alarm::B => {
+                regs.cr.modify(|_, w| w.alrbe().clear_bit());
+                while regs.isr.read().alrbwf().bit_is_clear() {}
+                regs.alrmbr.write(|w| unsafe { w.bits(alrmr) });
+                regs.alrmbssr.write(|w| unsafe { w.bits(alrmssr) });
+                regs.cr.modify(|_, w| w.alrbe().set_bit());
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Disables an alarm
+    pub fn disable_alarm(&mut self, alarm: Alarm) {
+        self.unprotected(|regs| match alarm {
+            Alarm::A => regs.cr.modify(|_, w| w.alrae().clear_bit()),
+            Alarm::B => regs.cr.modify(|_, w| w.alrbe().clear_bit()),
+        });
+    }
+
+    /// Returns true if the alarm has matched since its flag has been cleared
+    pub fn is_alarm_triggered(&self, alarm: Alarm) -> bool {
+        let isr = self.regs.isr.read();
+        match alarm {
+            Alarm::A => isr.alraf().bit_is_set(),
+            Alarm::B => isr.alrbf().bit_is_set(),
+        }
+    }
+
+    /// Starts the wakeup timer with the given clock
+    ///
+    /// The timer elapses every `reload + 1` clock cycles, with `CkSpreExtended` every
+    /// `reload + 2^16 + 1` cycles.
+    pub fn enable_wakeup_timer(&mut self, clock: WakeupClock, reload: u16) {
+        self.unprotected(|regs| {
+            regs.cr.modify(|_, w| w.wute().clear_bit());
+            while regs.isr.read().wutwf().bit_is_clear() {}
+            regs.wutr.write(|w| unsafe { w.bits(reload as u32) });
+            regs.cr
+                .modify(|_, w| unsafe { w.wucksel().bits(clock.bits()) }.wute().set_bit());
+        });
+    }
+
+    /// Starts the wakeup timer with the given period
+    ///
+    /// Picks the clock with the finest resolution that covers the period: the RTC clock divided
+    /// by 2 covers periods up to 4 s, divided by 4, 8 and 16 up to 8, 16 and 32 s. Longer periods
+    /// are counted in `ck_spre` cycles, at 1 Hz the period is truncated to whole seconds and may
+    /// be up to 36 hours.
+    ///
+    /// The periods are computed for an RTC clocked by a 32.768 kHz LSE, they are wrong with other
+    /// RTC clock sources.
+    pub fn start_wakeup_timer<T>(&mut self, period: T) -> Result<(), Error>
+    where
+        T: Into<MilliSeconds>,
+    {
+        let ms = period.into().0 as u64;
+
+        for &(clock, div) in &[
+            (WakeupClock::RtcDiv2, 2),
+            (WakeupClock::RtcDiv4, 4),
+            (WakeupClock::RtcDiv8, 8),
+            (WakeupClock::RtcDiv16, 16),
+        ] {
+            let ticks = ms * LSE_HZ as u64 / (div * 1000);
+            if (1..=0x1_0000).contains(&ticks) {
+                self.enable_wakeup_timer(clock, (ticks - 1) as u16);
+                return Ok(());
+            }
+        }
+
+        let prer = self.regs.prer.read();
+        let spre_hz =
+            LSE_HZ / ((prer.prediv_a().bits() as u32 + 1) * (prer.prediv_s().bits() as u32 + 1));
+        let ticks = ms * spre_hz as u64 / 1000;
+        match ticks {
+            1..=0x1_0000 => self.enable_wakeup_timer(WakeupClock::CkSpre, (ticks - 1) as u16),
+            0x1_0001..=0x2_0000 => {
+                self.enable_wakeup_timer(WakeupClock::CkSpreExtended, (ticks - 0x1_0000 - 1) as u16)
+            }
+            _ => return Err(Error::InvalidInputData),
+        }
+
+        Ok(())
+    }
+
+    /// Stops the wakeup timer
+    pub fn disable_wakeup_timer(&mut self) {
+        self.unprotected(|regs| regs.cr.modify(|_, w| w.wute().clear_bit()));
+    }
+
+    /// Returns true if the wakeup timer has elapsed since its flag has been cleared
+    pub fn is_wakeup_triggered(&self) -> bool {
+        self.regs.isr.read().wutf().bit_is_set()
+    }
+
+    /// Starts listening for an interrupt event
+    ///
+    /// The EXTI line is configured for a rising edge, which also wakes the MCU from Stop and
+    /// Standby mode.
+    pub fn listen(&mut self, exti: &mut EXTI, event: Event) {
+        let line = 1 << event.exti_line();
+        exti.rtsr.modify(|r, w| unsafe { w.bits(r.bits() | line) });
+        exti.imr.modify(|r, w| unsafe { w.bits(r.bits() | line) });
+
+        self.unprotected(|regs| match event {
+            Event::AlarmA => regs.cr.modify(|_, w| w.alraie().set_bit()),
+            Event::AlarmB => regs.cr.modify(|_, w| w.alrbie().set_bit()),
+            Event::Wakeup => regs.cr.modify(|_, w| w.wutie().set_bit()),
+        });
+    }
+
+    /// Stops listening for an interrupt event
+    pub fn unlisten(&mut self, exti: &mut EXTI, event: Event) {
+        self.unprotected(|regs| match event {
+            Event::AlarmA => regs.cr.modify(|_, w| w.alraie().clear_bit()),
+            Event::AlarmB => regs.cr.modify(|_, w| w.alrbie().clear_bit()),
+            Event::Wakeup => regs.cr.modify(|_, w| w.wutie().clear_bit()),
+        });
+
+        // The alarms share an EXTI line
+        let cr = self.regs.cr.read();
+        let line_used = match event {
+            Event::AlarmA | Event::AlarmB => cr.alraie().bit_is_set() || cr.alrbie().bit_is_set(),
+            Event::Wakeup => false,
+        };
+        if !line_used {
+            let line = 1 << event.exti_line();
+            exti.imr.modify(|r, w| unsafe { w.bits(r.bits() & !line) });
+            exti.rtsr.modify(|r, w| unsafe { w.bits(r.bits() & !line) });
+        }
+    }
+
+    /// Clears the flag of an event and the pending bit of its EXTI line
+    pub fn clear_interrupt(&mut self, event: Event) {
+        match event {
+            Event::AlarmA => self.regs.isr.modify(|_, w| w.alraf().clear_bit()),
+            Event::AlarmB => self.regs.isr.modify(|_, w| w.alrbf().clear_bit()),
+            Event::Wakeup => self.regs.isr.modify(|_, w| w.wutf().clear_bit()),
+        }
+        unsafe { (*EXTI::ptr()).pr.write(|w| w.bits(1 << event.exti_line())) };
+    }
+
+    /// Disables the write protection of the RTC registers while the closure runs, without
+    /// entering init mode
+    fn unprotected<F>(&mut self, closure: F)
+    where
+        F: FnOnce(&mut RTC),
+    {
+        self.regs.wpr.write(|w| unsafe { w.bits(0xCA) });
+        self.regs.wpr.write(|w| unsafe { w.bits(0x53) });
+        closure(&mut self.regs);
+        self.regs.wpr.write(|w| unsafe { w.bits(0xFF) });
+    }
+
     /// As described in Section 27.3.7 in RM0316,
     /// this function is used to disable write protection
     /// when modifying an RTC register
@@ -108,6 +513,90 @@ impl Rtc {
     }
 }
 
+/// Number of RTC backup registers
+pub const BACKUP_REGISTERS: usize = 20;
+
+/// Size of the backup SRAM in bytes
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+pub const BACKUP_SRAM_SIZE: usize = 4096;
+
+/// Write access to the backup domain
+///
+/// Creating the token enables the power interface and disables the backup domain write
+/// protection, the same way [`Rtc::new`] does.
+pub struct BackupDomain {
+    _private: (),
+}
+
+impl BackupDomain {
+    /// Enables write access to the backup domain
+    pub fn new(pwr: &mut PWR) -> Self {
+        unsafe {
+            unlock(&(*RCC::ptr()), pwr);
+        }
+
+        BackupDomain { _private: () }
+    }
+
+    /// Enables the backup regulator and returns the backup SRAM
+    ///
+    /// The backup SRAM keeps its contents across resets, in Standby and in VBAT mode. Returns
+    /// `None` if the backup SRAM has been taken before.
+    #[cfg(any(
+        feature = "stm32f405",
+        feature = "stm32f407",
+        feature = "stm32f415",
+        feature = "stm32f417",
+        feature = "stm32f427",
+        feature = "stm32f429",
+        feature = "stm32f437",
+        feature = "stm32f439",
+        feature = "stm32f446",
+        feature = "stm32f469",
+        feature = "stm32f479"
+    ))]
+    pub fn backup_sram(&mut self) -> Option<&'static mut [u8; BACKUP_SRAM_SIZE]> {
+        use core::sync::atomic::{AtomicBool, Ordering};
+
+        const BKPSRAM_BASE: usize = 0x4002_4000;
+        static TAKEN: AtomicBool = AtomicBool::new(false);
+
+        if TAKEN.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+
+        unsafe {
+            // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+            let rcc = &(*RCC::ptr());
+            // Set AHB1 - Bit 18 (BKPSRAMEN)
+            bb::set(&rcc.ahb1enr, 18);
+        }
+
+        // Stall the pipeline to work around erratum 2.1.13 (DM00037591)
+        cortex_m::asm::dsb();
+
+        // NOTE(unsafe) only the backup regulator bit is modified, BRE requires DBP set by `new`
+        let pwr = unsafe { &*PWR::ptr() };
+        pwr.csr.modify(|_, w| w.bre().set_bit());
+        while pwr.csr.read().brr().bit_is_clear() {}
+
+        // NOTE(unsafe) the memory is only handed out once, guarded by `TAKEN`
+        Some(unsafe { &mut *(BKPSRAM_BASE as *mut [u8; BACKUP_SRAM_SIZE]) })
+    }
+}
+
 impl Rtcc for Rtc {
     // ** Assumes 1970-01-01 00:00:00 Epoch **
     type Error = Error;
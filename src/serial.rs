@@ -15,9 +15,18 @@
 //! the embedded-hal read and write traits with `u16` as the word type. You can use these
 //! implementations for 9-bit words.
 //!
+//! # DMA
+//!
+//! After splitting a `Serial`, `Rx::read_dma` and `Tx::write_dma` hand a buffer to the
+//! UART/USART using a DMA stream and return the running `dma::Transfer`. For variable length
+//! frames, `Rx::read_dma_idle` completes a reception as soon as the line goes idle.
+//! `RxRingBuffer` keeps a circular DMA transfer running for continuous reception.
+//!
 
 use core::fmt;
 use core::marker::PhantomData;
+use core::ptr;
+use core::sync::atomic::{compiler_fence, Ordering};
 
 use embedded_hal::blocking;
 use embedded_hal::prelude::*;
@@ -392,7 +401,12 @@ use crate::gpio::AF11;
 use crate::gpio::{Alternate, AF7, AF8};
 use crate::rcc::Clocks;
 
-use crate::dma::traits::PeriAddress;
+use crate::dma::{
+    self,
+    traits::{Channel, DMASet, PeriAddress, Stream},
+    DMAError, MemoryToPeripheral, PeripheralToMemory, Transfer,
+};
+use embedded_dma::{StaticReadBuffer, StaticWriteBuffer};
 
 /// Serial error
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
@@ -1969,6 +1983,360 @@ where
     }
 }
 
+impl<USART, WORD> Rx<USART, WORD>
+where
+    USART: Instance,
+{
+    /// Starts listening for the idle line interrupt
+    ///
+    /// Note, you will also have to enable the corresponding interrupt
+    /// in the NVIC to start receiving events.
+    pub fn listen_idle(&mut self) {
+        unsafe { (*USART::ptr()).cr1.modify(|_, w| w.idleie().set_bit()) }
+    }
+
+    /// Stop listening for the idle line interrupt
+    pub fn unlisten_idle(&mut self) {
+        unsafe { (*USART::ptr()).cr1.modify(|_, w| w.idleie().clear_bit()) }
+    }
+
+    /// Return true if the line idle status is set
+    pub fn is_idle(&self) -> bool {
+        unsafe { (*USART::ptr()).sr.read().idle().bit_is_set() }
+    }
+
+    /// Clear the line idle status flag
+    ///
+    /// The flag is cleared by a read of the status register followed by a read of the data
+    /// register. If a byte has already been received the data register isn't read, so the byte
+    /// isn't lost, and the flag is cleared when that byte is read.
+    pub fn clear_idle_interrupt(&mut self) {
+        unsafe {
+            let sr = (*USART::ptr()).sr.read();
+            if sr.rxne().bit_is_clear() {
+                let _ = (*USART::ptr()).dr.read();
+            }
+        }
+    }
+}
+
+impl<USART> Rx<USART, u8>
+where
+    USART: Instance,
+{
+    /// Receives `buf.len()` bytes into `buf` using DMA
+    ///
+    /// The DMA receive request (DMAR) is enabled and the stream is started before returning. The
+    /// transfer complete flag of the returned `Transfer` is set once the buffer has been filled.
+    pub fn read_dma<STREAM, CHANNEL, BUF>(
+        self,
+        stream: STREAM,
+        buf: BUF,
+    ) -> Transfer<STREAM, CHANNEL, Self, PeripheralToMemory, BUF>
+    where
+        STREAM: Stream,
+        CHANNEL: Channel,
+        Self: DMASet<STREAM, CHANNEL, PeripheralToMemory>,
+        BUF: StaticWriteBuffer<Word = u8>,
+    {
+        let config = dma::config::DmaConfig::default()
+            .memory_increment(true)
+            .transfer_complete_interrupt(true);
+        let mut transfer = Transfer::init_peripheral_to_memory(stream, self, buf, None, config);
+        transfer.start(|_| unsafe { (*USART::ptr()).cr3.modify(|_, w| w.dmar().enabled()) });
+        transfer
+    }
+
+    /// Receives variable length frames into `buf` using DMA
+    ///
+    /// A frame is considered complete once the line has been idle for one character time after
+    /// the last received byte, or once `buf` has been filled. The idle line interrupt is enabled,
+    /// see [`IdleRxTransfer`] for how to retrieve the received frames.
+    pub fn read_dma_idle<STREAM, CHANNEL, BUF>(
+        mut self,
+        stream: STREAM,
+        mut buf: BUF,
+    ) -> IdleRxTransfer<USART, STREAM, CHANNEL, BUF>
+    where
+        STREAM: Stream,
+        CHANNEL: Channel,
+        Self: DMASet<STREAM, CHANNEL, PeripheralToMemory>,
+        BUF: StaticWriteBuffer<Word = u8>,
+    {
+        // NOTE(unsafe) Only the length is used, the buffer isn't accessed
+        let (_, len) = unsafe { buf.write_buffer() };
+
+        self.clear_idle_interrupt();
+        self.listen_idle();
+
+        IdleRxTransfer {
+            transfer: self.read_dma(stream, buf),
+            len: len as u16,
+        }
+    }
+}
+
+impl<USART> Tx<USART, u8>
+where
+    USART: Instance,
+{
+    /// Transmits the contents of `buf` using DMA
+    ///
+    /// The DMA transmit request (DMAT) is enabled and the stream is started before returning. The
+    /// transfer complete flag of the returned `Transfer` is set once the last byte has been
+    /// written to the data register, use `flush` to wait until it has been shifted out.
+    pub fn write_dma<STREAM, CHANNEL, BUF>(
+        self,
+        stream: STREAM,
+        buf: BUF,
+    ) -> Transfer<STREAM, CHANNEL, Self, MemoryToPeripheral, BUF>
+    where
+        STREAM: Stream,
+        CHANNEL: Channel,
+        Self: DMASet<STREAM, CHANNEL, MemoryToPeripheral>,
+        BUF: StaticReadBuffer<Word = u8>,
+    {
+        let config = dma::config::DmaConfig::default()
+            .memory_increment(true)
+            .transfer_complete_interrupt(true);
+        let mut transfer = Transfer::init_memory_to_peripheral(stream, self, buf, None, config);
+        transfer.start(|_| unsafe {
+            // Clear the transmission complete flag before the first byte is written
+            (*USART::ptr()).sr.modify(|_, w| w.tc().clear_bit());
+            (*USART::ptr()).cr3.modify(|_, w| w.dmat().enabled());
+        });
+        transfer
+    }
+}
+
+/// DMA reception of idle line terminated frames
+///
+/// Created by [`Rx::read_dma_idle`]. Once a frame has been received, either the USART idle line
+/// interrupt or the DMA transfer complete interrupt fires, call [`next_frame`] from there to swap
+/// in a new buffer and get the received frame back.
+///
+/// [`next_frame`]: IdleRxTransfer::next_frame
+pub struct IdleRxTransfer<USART, STREAM, CHANNEL, BUF>
+where
+    USART: Instance,
+    STREAM: Stream,
+{
+    transfer: Transfer<STREAM, CHANNEL, Rx<USART>, PeripheralToMemory, BUF>,
+    len: u16,
+}
+
+impl<USART, STREAM, CHANNEL, BUF> IdleRxTransfer<USART, STREAM, CHANNEL, BUF>
+where
+    USART: Instance,
+    STREAM: Stream,
+    CHANNEL: Channel,
+    Rx<USART>: DMASet<STREAM, CHANNEL, PeripheralToMemory>,
+    BUF: StaticWriteBuffer<Word = u8>,
+{
+    /// Return true if a frame has been received, either because the line went idle or because the
+    /// buffer is full
+    pub fn is_frame_complete(&self) -> bool {
+        let idle = unsafe { (*USART::ptr()).sr.read().idle().bit_is_set() };
+        idle || STREAM::get_transfer_complete_flag()
+    }
+
+    /// Number of bytes received into the current buffer so far
+    pub fn received(&self) -> usize {
+        (self.len - self.transfer.number_of_transfers()) as usize
+    }
+
+    /// Replaces the current buffer with `new_buf` and restarts reception
+    ///
+    /// Returns the previous buffer together with the number of bytes of the frame it contains. A
+    /// `NotReady` error is returned together with `new_buf` if no frame has been completed yet.
+    pub fn next_frame(&mut self, mut new_buf: BUF) -> Result<(BUF, usize), DMAError<BUF>> {
+        if !self.is_frame_complete() {
+            return Err(DMAError::NotReady(new_buf));
+        }
+
+        // Stop the stream first so the number of received bytes can't change anymore and the
+        // data register isn't read while the DMA could still be reading it. A byte of the next
+        // frame which is already waiting in the data register is left for the new transfer.
+        self.transfer.pause(|_| {});
+        let received = self.received();
+        unsafe {
+            // Same sequence as `Rx::clear_idle_interrupt`
+            let usart = &*USART::ptr();
+            if usart.sr.read().rxne().bit_is_clear() {
+                let _ = usart.dr.read();
+            }
+        }
+
+        // NOTE(unsafe) Only the length is used, the buffer isn't accessed
+        let (_, len) = unsafe { new_buf.write_buffer() };
+        let (old_buf, _) = self.transfer.next_transfer(new_buf)?;
+        self.len = len as u16;
+
+        Ok((old_buf, received))
+    }
+
+    /// Stops reception and returns the underlying resources
+    ///
+    /// The buffer is returned together with the number of bytes received into it.
+    pub fn free(self) -> (STREAM, Rx<USART>, BUF, usize) {
+        let received = self.received();
+        let (stream, mut rx, buf, _) = self.transfer.free();
+        rx.unlisten_idle();
+        unsafe { (*USART::ptr()).cr3.modify(|_, w| w.dmar().disabled()) };
+        (stream, rx, buf, received)
+    }
+}
+
+/// Continuous DMA reception into a ring buffer
+///
+/// The DMA stream runs in circular mode and never stops, the received bytes are tracked by
+/// comparing the number of remaining transfers (NDTR) with the read position. The half transfer
+/// and transfer complete interrupts of the stream are enabled, both flags are cleared by
+/// [`available`] and [`read`], so they can be called from the DMA interrupt handler.
+///
+/// [`available`]: RxRingBuffer::available
+/// [`read`]: RxRingBuffer::read
+pub struct RxRingBuffer<USART, STREAM, CHANNEL, BUF>
+where
+    USART: Instance,
+    STREAM: Stream,
+{
+    transfer: Transfer<STREAM, CHANNEL, Rx<USART>, PeripheralToMemory, BUF>,
+    buf: *const u8,
+    len: usize,
+    read_pos: usize,
+    // The DMA has wrapped around since `read_pos` was last wrapped around
+    wrapped: bool,
+}
+
+// NOTE(unsafe) The buffer pointer is owned by the contained transfer
+unsafe impl<USART, STREAM, CHANNEL, BUF> Send for RxRingBuffer<USART, STREAM, CHANNEL, BUF>
+where
+    USART: Instance,
+    STREAM: Stream + Send,
+    CHANNEL: Send,
+    BUF: Send,
+{
+}
+
+impl<USART, STREAM, CHANNEL, BUF> RxRingBuffer<USART, STREAM, CHANNEL, BUF>
+where
+    USART: Instance,
+    STREAM: Stream,
+    CHANNEL: Channel,
+    Rx<USART>: DMASet<STREAM, CHANNEL, PeripheralToMemory>,
+    BUF: StaticWriteBuffer<Word = u8>,
+{
+    /// Starts receiving into `buf` and keeps the DMA stream running until `free` is called
+    ///
+    /// # Panics
+    ///
+    /// * When `buf` is empty or longer than 65535 bytes.
+    pub fn new(rx: Rx<USART>, stream: STREAM, mut buf: BUF) -> Self {
+        // NOTE(unsafe) The buffer is only read through this pointer, the DMA is the only writer
+        let (ptr, len) = unsafe { buf.write_buffer() };
+        assert!(len > 0 && len <= u16::MAX as usize);
+
+        let config = dma::config::DmaConfig::default()
+            .memory_increment(true)
+            .circular_buffer(true)
+            .half_transfer_interrupt(true)
+            .transfer_complete_interrupt(true);
+        let mut transfer = Transfer::init_peripheral_to_memory(stream, rx, buf, None, config);
+        transfer.start(|_| unsafe { (*USART::ptr()).cr3.modify(|_, w| w.dmar().enabled()) });
+
+        RxRingBuffer {
+            transfer,
+            buf: ptr as *const u8,
+            len,
+            read_pos: 0,
+            wrapped: false,
+        }
+    }
+
+    /// Returns the number of bytes that can be read without blocking
+    ///
+    /// Returns `Error::Overrun` if the DMA has overwritten bytes that weren't read yet, call
+    /// `clear` to discard the buffer contents and resume reception.
+    pub fn available(&mut self) -> Result<usize, Error> {
+        let (write_pos, complete) = self.write_pos();
+        if complete {
+            if self.wrapped {
+                return Err(Error::Overrun);
+            }
+            self.wrapped = true;
+        }
+
+        if self.wrapped {
+            if write_pos > self.read_pos {
+                return Err(Error::Overrun);
+            }
+            Ok(self.len - self.read_pos + write_pos)
+        } else {
+            Ok(write_pos - self.read_pos)
+        }
+    }
+
+    /// Reads up to `buf.len()` received bytes into `buf`, returns the number of bytes read
+    pub fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let n = self.available()?.min(buf.len());
+
+        // "Subsequent reads and writes cannot be moved ahead of preceding reads"
+        compiler_fence(Ordering::Acquire);
+
+        for byte in buf[..n].iter_mut() {
+            // NOTE(unsafe) `read_pos` is always smaller than `len`
+            *byte = unsafe { ptr::read_volatile(self.buf.add(self.read_pos)) };
+            self.read_pos += 1;
+            if self.read_pos == self.len {
+                self.read_pos = 0;
+                self.wrapped = false;
+            }
+        }
+
+        // Check the data wasn't overwritten while it was being copied
+        if n + self.available()? > self.len {
+            return Err(Error::Overrun);
+        }
+        Ok(n)
+    }
+
+    /// Discards all received bytes and clears an overrun condition
+    pub fn clear(&mut self) {
+        self.read_pos = self.write_pos().0;
+        self.wrapped = false;
+    }
+
+    /// Stops reception and returns the underlying resources
+    pub fn free(self) -> (STREAM, Rx<USART>, BUF) {
+        let (stream, rx, buf, _) = self.transfer.free();
+        unsafe { (*USART::ptr()).cr3.modify(|_, w| w.dmar().disabled()) };
+        (stream, rx, buf)
+    }
+
+    // Current write position of the DMA, and whether it wrapped around since the last call
+    fn write_pos(&mut self) -> (usize, bool) {
+        // Make sure the transfer complete flag and the number of remaining transfers are
+        // consistent with each other
+        let (complete, remaining) = loop {
+            let complete = STREAM::get_transfer_complete_flag();
+            let remaining = self.transfer.number_of_transfers() as usize;
+            if complete == STREAM::get_transfer_complete_flag() {
+                break (complete, remaining);
+            }
+        };
+
+        if STREAM::get_half_transfer_flag() {
+            self.transfer.clear_half_transfer_interrupt();
+        }
+        if complete {
+            self.transfer.clear_transfer_complete_interrupt();
+        }
+
+        ((self.len - remaining) % self.len, complete)
+    }
+}
+
 impl<USART, PINS, WORD> Serial<USART, PINS, WORD>
 where
     PINS: Pins<USART>,
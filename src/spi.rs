@@ -1,7 +1,14 @@
+use core::marker::PhantomData;
 use core::ops::Deref;
 use core::ptr;
 
 use crate::bb;
+use crate::dma::{
+    config::DmaConfig,
+    traits::{Channel, DMASet, PeriAddress, Stream},
+    MemoryToPeripheral, PeripheralToMemory, Transfer,
+};
+use embedded_dma::{StaticReadBuffer, StaticWriteBuffer};
 use embedded_hal::spi;
 pub use embedded_hal::spi::{Mode, Phase, Polarity};
 
@@ -364,10 +371,14 @@ pub enum Error {
     _Extensible,
 }
 
-pub trait Pins<SPI> {}
+pub trait Pins<SPI> {
+    #[doc(hidden)]
+    const NSS: bool = false;
+}
 pub trait PinSck<SPI> {}
 pub trait PinMiso<SPI> {}
 pub trait PinMosi<SPI> {}
+pub trait PinNss<SPI> {}
 
 impl<SPI, SCK, MISO, MOSI> Pins<SPI> for (SCK, MISO, MOSI)
 where
@@ -377,6 +388,18 @@ where
 {
 }
 
+/// Pins with a hardware managed NSS pin, which is an output in master mode and an input in slave
+/// mode
+impl<SPI, SCK, MISO, MOSI, NSS> Pins<SPI> for (SCK, MISO, MOSI, NSS)
+where
+    SCK: PinSck<SPI>,
+    MISO: PinMiso<SPI>,
+    MOSI: PinMosi<SPI>,
+    NSS: PinNss<SPI>,
+{
+    const NSS: bool = true;
+}
+
 /// A filler type for when the SCK pin is unnecessary
 pub struct NoSck;
 /// A filler type for when the Miso pin is unnecessary
@@ -703,6 +726,119 @@ pins! {
         MOSI: [PC1<Alternate<AF5>>]
 }
 
+macro_rules! nss_pins {
+    ($($SPIX:ty: [$($NSS:ty),*])+) => {
+        $(
+            $(
+                impl PinNss<$SPIX> for $NSS {}
+            )*
+        )+
+    }
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+nss_pins! {
+    SPI1: [
+        crate::gpio::gpioa::PA4<Alternate<AF5>>,
+        crate::gpio::gpioa::PA15<Alternate<AF5>>
+    ]
+    SPI2: [
+        crate::gpio::gpiob::PB9<Alternate<AF5>>,
+        crate::gpio::gpiob::PB12<Alternate<AF5>>
+    ]
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+nss_pins! {
+    SPI3: [
+        crate::gpio::gpioa::PA4<Alternate<AF6>>,
+        crate::gpio::gpioa::PA15<Alternate<AF6>>
+    ]
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+nss_pins! {
+    SPI4: [
+        crate::gpio::gpioe::PE4<Alternate<AF5>>,
+        crate::gpio::gpioe::PE11<Alternate<AF5>>
+    ]
+}
+
+#[cfg(any(
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f423"
+))]
+nss_pins! {
+    SPI5: [crate::gpio::gpiob::PB1<Alternate<AF6>>]
+}
+
+#[cfg(any(
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+nss_pins! {
+    SPI5: [
+        crate::gpio::gpiof::PF6<Alternate<AF5>>,
+        crate::gpio::gpioh::PH5<Alternate<AF5>>
+    ]
+    SPI6: [crate::gpio::gpiog::PG8<Alternate<AF5>>]
+}
+
 /// Interrupt events
 pub enum Event {
     /// New data has been received
@@ -713,10 +849,139 @@ pub enum Event {
     Error,
 }
 
+mod private {
+    pub trait Sealed {}
+}
+
+/// SPI peripheral instance
+pub trait Instance: private::Sealed + Deref<Target = spi1::RegisterBlock> {
+    #[doc(hidden)]
+    unsafe fn enable_clock(rcc: &crate::stm32::rcc::RegisterBlock);
+}
+
+macro_rules! instance {
+    ($($SPI:ident: ($apbXenr:ident, $en_bit:expr),)+) => {
+        $(
+            impl private::Sealed for $SPI {}
+            impl Instance for $SPI {
+                unsafe fn enable_clock(rcc: &crate::stm32::rcc::RegisterBlock) {
+                    bb::set(&rcc.$apbXenr, $en_bit);
+
+                    // Stall the pipeline to work around erratum 2.1.13 (DM00037591)
+                    cortex_m::asm::dsb();
+                }
+            }
+        )+
+    }
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+instance! {
+    SPI1: (apb2enr, 12),
+    SPI2: (apb1enr, 14),
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+instance! {
+    SPI3: (apb1enr, 15),
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+instance! {
+    SPI4: (apb2enr, 13),
+}
+
+#[cfg(any(
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+instance! {
+    SPI5: (apb2enr, 20),
+}
+
+#[cfg(any(
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+instance! {
+    SPI6: (apb2enr, 21),
+}
+
+/// Order in which the bits of a frame are sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitOrder {
+    /// Most significant bit first, the default
+    MsbFirst,
+    /// Least significant bit first
+    LsbFirst,
+}
+
 #[derive(Debug)]
-pub struct Spi<SPI, PINS> {
+pub struct Spi<SPI, PINS, WORD = u8> {
     spi: SPI,
     pins: PINS,
+    _word: PhantomData<WORD>,
 }
 
 #[cfg(any(
@@ -755,7 +1020,13 @@ impl<PINS> Spi<SPI1, PINS> {
             cortex_m::asm::dsb();
         }
 
-        Spi { spi, pins }.init(mode, freq, clocks.pclk2())
+        Spi {
+            spi,
+            pins,
+            _word: PhantomData,
+        }
+        .init(mode, freq, clocks.pclk2())
+        .init_nss()
     }
 }
 
@@ -795,7 +1066,13 @@ impl<PINS> Spi<SPI2, PINS> {
             cortex_m::asm::dsb();
         }
 
-        Spi { spi, pins }.init(mode, freq, clocks.pclk1())
+        Spi {
+            spi,
+            pins,
+            _word: PhantomData,
+        }
+        .init(mode, freq, clocks.pclk1())
+        .init_nss()
     }
 }
 
@@ -834,7 +1111,13 @@ impl<PINS> Spi<SPI3, PINS> {
             cortex_m::asm::dsb();
         }
 
-        Spi { spi, pins }.init(mode, freq, clocks.pclk1())
+        Spi {
+            spi,
+            pins,
+            _word: PhantomData,
+        }
+        .init(mode, freq, clocks.pclk1())
+        .init_nss()
     }
 }
 
@@ -869,7 +1152,13 @@ impl<PINS> Spi<SPI4, PINS> {
             cortex_m::asm::dsb();
         }
 
-        Spi { spi, pins }.init(mode, freq, clocks.pclk2())
+        Spi {
+            spi,
+            pins,
+            _word: PhantomData,
+        }
+        .init(mode, freq, clocks.pclk2())
+        .init_nss()
     }
 }
 
@@ -903,7 +1192,13 @@ impl<PINS> Spi<SPI5, PINS> {
             cortex_m::asm::dsb();
         }
 
-        Spi { spi, pins }.init(mode, freq, clocks.pclk2())
+        Spi {
+            spi,
+            pins,
+            _word: PhantomData,
+        }
+        .init(mode, freq, clocks.pclk2())
+        .init_nss()
     }
 }
 
@@ -932,7 +1227,87 @@ impl<PINS> Spi<SPI6, PINS> {
             cortex_m::asm::dsb();
         }
 
-        Spi { spi, pins }.init(mode, freq, clocks.pclk2())
+        Spi {
+            spi,
+            pins,
+            _word: PhantomData,
+        }
+        .init(mode, freq, clocks.pclk2())
+        .init_nss()
+    }
+}
+
+impl<SPI, PINS> Spi<SPI, PINS>
+where
+    SPI: Instance,
+    PINS: Pins<SPI>,
+{
+    /// Configures the SPI peripheral as a slave
+    ///
+    /// If the pins include an NSS pin, the slave only responds while the master pulls NSS low,
+    /// otherwise it is always selected. The data to send has to be written before the master
+    /// starts clocking, e.g. from the RXNE interrupt after reading the previous byte.
+    pub fn new_slave(spi: SPI, pins: PINS, mode: Mode) -> Self {
+        unsafe {
+            // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+            let rcc = &(*RCC::ptr());
+
+            // Enable clock.
+            SPI::enable_clock(rcc);
+        }
+
+        // disable SS output
+        spi.cr2.write(|w| w.ssoe().clear_bit());
+
+        // mstr: slave configuration
+        // lsbfirst: MSB first
+        // ssm: hardware slave management if there is an NSS pin
+        // ssi: selected if there is no NSS pin
+        // dff: 8 bit frames
+        // bidimode: 2-line unidirectional
+        // spe: enable the SPI bus
+        spi.cr1.write(|w| {
+            w.cpha()
+                .bit(mode.phase == Phase::CaptureOnSecondTransition)
+                .cpol()
+                .bit(mode.polarity == Polarity::IdleHigh)
+                .mstr()
+                .clear_bit()
+                .lsbfirst()
+                .clear_bit()
+                .ssm()
+                .bit(!PINS::NSS)
+                .ssi()
+                .clear_bit()
+                .rxonly()
+                .clear_bit()
+                .dff()
+                .clear_bit()
+                .bidimode()
+                .clear_bit()
+                .spe()
+                .set_bit()
+        });
+
+        Spi {
+            spi,
+            pins,
+            _word: PhantomData,
+        }
+    }
+
+    /// Lets the peripheral drive the NSS pin if there is one, NSS is low while the peripheral is
+    /// enabled
+    fn init_nss(self) -> Self {
+        if PINS::NSS {
+            self.spi.cr1.modify(|_, w| w.spe().clear_bit());
+            self.spi.cr2.modify(|_, w| w.ssoe().set_bit());
+            self.spi
+                .cr1
+                .modify(|_, w| w.ssm().clear_bit().spe().set_bit());
+        }
+
+        self
     }
 }
 
@@ -991,6 +1366,121 @@ where
         self
     }
 
+    /// Converts this Spi into a version that sends and receives 16 bit frames as `u16` values
+    pub fn with_u16_data(self) -> Spi<SPI, PINS, u16> {
+        self.set_dff(true);
+        Spi {
+            spi: self.spi,
+            pins: self.pins,
+            _word: PhantomData,
+        }
+    }
+}
+
+impl<SPI, PINS> Spi<SPI, PINS, u16>
+where
+    SPI: Deref<Target = spi1::RegisterBlock>,
+{
+    /// Converts this Spi into a version that sends and receives 8 bit frames as `u8` values
+    pub fn with_u8_data(self) -> Spi<SPI, PINS, u8> {
+        self.set_dff(false);
+        Spi {
+            spi: self.spi,
+            pins: self.pins,
+            _word: PhantomData,
+        }
+    }
+}
+
+impl<SPI, PINS, WORD> Spi<SPI, PINS, WORD>
+where
+    SPI: Deref<Target = spi1::RegisterBlock>,
+{
+    /// Sets the order in which the bits of a frame are sent
+    pub fn set_bit_order(&mut self, order: BitOrder) {
+        self.spi.cr1.modify(|_, w| w.spe().clear_bit());
+        self.spi.cr1.modify(|_, w| {
+            w.lsbfirst()
+                .bit(order == BitOrder::LsbFirst)
+                .spe()
+                .set_bit()
+        });
+    }
+
+    /// Enables hardware CRC calculation with the given `polynomial`
+    ///
+    /// The CRC is as wide as a frame, i.e. 8 bit unless 16 bit frames are used. It is only sent
+    /// and checked by [`transfer_with_crc`](Self::transfer_with_crc),
+    /// [`write_with_crc`](Self::write_with_crc) and the DMA transfers, the `embedded-hal` traits
+    /// send plain data.
+    pub fn enable_crc(&mut self, polynomial: u16) {
+        self.spi.cr1.modify(|_, w| w.spe().clear_bit());
+        self.spi.crcpr.write(|w| w.crcpoly().bits(polynomial));
+        self.spi
+            .cr1
+            .modify(|_, w| w.crcen().set_bit().spe().set_bit());
+    }
+
+    /// Disables hardware CRC calculation
+    pub fn disable_crc(&mut self) {
+        self.spi.cr1.modify(|_, w| w.spe().clear_bit());
+        self.spi
+            .cr1
+            .modify(|_, w| w.crcen().clear_bit().spe().set_bit());
+    }
+
+    /// Returns the CRC calculated over the received frames
+    pub fn rx_crc(&self) -> u16 {
+        self.spi.rxcrcr.read().rx_crc().bits()
+    }
+
+    /// Returns the CRC calculated over the sent frames
+    pub fn tx_crc(&self) -> u16 {
+        self.spi.txcrcr.read().tx_crc().bits()
+    }
+
+    /// Restarts the CRC calculation if it is enabled
+    fn reset_crc(&self) {
+        if self.spi.cr1.read().crcen().bit_is_set() {
+            // The CRC registers are cleared by disabling CRC while the SPI is disabled
+            self.spi.cr1.modify(|_, w| w.spe().clear_bit());
+            self.spi.cr1.modify(|_, w| w.crcen().clear_bit());
+            self.spi
+                .cr1
+                .modify(|_, w| w.crcen().set_bit().spe().set_bit());
+        }
+    }
+
+    /// Drops the CRC frame, which is received after the data
+    fn recv_crc(&self) {
+        while self.spi.sr.read().rxne().bit_is_clear() {}
+        let _ = self.spi.dr.read();
+    }
+
+    /// Receives the CRC frame following the data and checks it
+    ///
+    /// Returns `Ok` if CRC is disabled.
+    fn check_crc(&self) -> Result<(), Error> {
+        if self.spi.cr1.read().crcen().bit_is_clear() {
+            return Ok(());
+        }
+
+        self.recv_crc();
+        if self.spi.sr.read().crcerr().bit_is_set() {
+            self.spi.sr.modify(|_, w| w.crcerr().clear_bit());
+            Err(Error::Crc)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Selects 16 bit (`true`) or 8 bit frames, which can only be changed while the SPI is
+    /// disabled
+    fn set_dff(&self, dff: bool) {
+        self.spi.cr1.modify(|_, w| w.spe().clear_bit());
+        self.spi.cr1.modify(|_, w| w.dff().bit(dff).spe().set_bit());
+    }
+
     /// Enable interrupts for the given `event`:
     ///  - Received data ready to be read (RXNE)
     ///  - Transmit data register empty (TXE)
@@ -1039,18 +1529,265 @@ where
         self.spi.sr.read().ovr().bit_is_set()
     }
 
+    /// Return `true` if the CRCERR flag is set, i.e. the received CRC didn't match the
+    /// calculated one.
+    pub fn is_crcerr(&self) -> bool {
+        self.spi.sr.read().crcerr().bit_is_set()
+    }
+
+    /// Clears the CRCERR flag
+    pub fn clear_crcerr(&mut self) {
+        self.spi.sr.modify(|_, w| w.crcerr().clear_bit());
+    }
+
     pub fn free(self) -> (SPI, PINS) {
         (self.spi, self.pins)
     }
+
+    /// Sends `tx_buf` and receives into `rx_buf` at the same time using two DMA streams
+    ///
+    /// If CRC is enabled the CRC is sent after the last frame and the received one is checked
+    /// when the transfer is waited for, a mismatch sets the CRCERR flag.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffers have different lengths.
+    pub fn transfer_dma<TXSTREAM, TXCHANNEL, RXSTREAM, RXCHANNEL, TXBUF, RXBUF>(
+        self,
+        tx_stream: TXSTREAM,
+        rx_stream: RXSTREAM,
+        tx_buf: TXBUF,
+        mut rx_buf: RXBUF,
+    ) -> DmaTransfer<SPI, PINS, TXSTREAM, TXCHANNEL, RXSTREAM, RXCHANNEL, TXBUF, RXBUF, WORD>
+    where
+        TXSTREAM: Stream,
+        TXCHANNEL: Channel,
+        RXSTREAM: Stream,
+        RXCHANNEL: Channel,
+        Tx<SPI, WORD>: DMASet<TXSTREAM, TXCHANNEL, MemoryToPeripheral>,
+        Rx<SPI, WORD>: DMASet<RXSTREAM, RXCHANNEL, PeripheralToMemory>,
+        TXBUF: StaticReadBuffer<Word = WORD>,
+        RXBUF: StaticWriteBuffer<Word = WORD>,
+    {
+        // NOTE(unsafe) Only the lengths are used, the buffers aren't accessed
+        let (_, tx_len) = unsafe { tx_buf.read_buffer() };
+        let (_, rx_len) = unsafe { rx_buf.write_buffer() };
+        assert_eq!(tx_len, rx_len);
+
+        self.clear_rx();
+        self.reset_crc();
+
+        let config = DmaConfig::default().memory_increment(true);
+        let mut rx = Transfer::init_peripheral_to_memory(
+            rx_stream,
+            self.rx(),
+            rx_buf,
+            None,
+            config.transfer_complete_interrupt(true),
+        );
+        let mut tx =
+            Transfer::init_memory_to_peripheral(tx_stream, self.tx(), tx_buf, None, config);
+
+        // The receive stream has to be running before the first byte is sent
+        rx.start(|_| self.spi.cr2.modify(|_, w| w.rxdmaen().set_bit()));
+        tx.start(|_| self.spi.cr2.modify(|_, w| w.txdmaen().set_bit()));
+
+        DmaTransfer { spi: self, tx, rx }
+    }
+
+    /// Sends `buf` using a DMA stream, the received bytes are discarded
+    ///
+    /// If CRC is enabled the CRC is sent after the last frame.
+    pub fn write_dma<STREAM, CHANNEL, BUF>(
+        self,
+        stream: STREAM,
+        buf: BUF,
+    ) -> DmaWrite<SPI, PINS, STREAM, CHANNEL, BUF, WORD>
+    where
+        STREAM: Stream,
+        CHANNEL: Channel,
+        Tx<SPI, WORD>: DMASet<STREAM, CHANNEL, MemoryToPeripheral>,
+        BUF: StaticReadBuffer<Word = WORD>,
+    {
+        let config = DmaConfig::default()
+            .memory_increment(true)
+            .transfer_complete_interrupt(true);
+        self.reset_crc();
+        let mut tx = Transfer::init_memory_to_peripheral(stream, self.tx(), buf, None, config);
+        tx.start(|_| self.spi.cr2.modify(|_, w| w.txdmaen().set_bit()));
+
+        DmaWrite { spi: self, tx }
+    }
+
+    fn tx(&self) -> Tx<SPI, WORD> {
+        Tx {
+            address: &self.spi.dr as *const _ as u32,
+            _spi: PhantomData,
+            _word: PhantomData,
+        }
+    }
+
+    fn rx(&self) -> Rx<SPI, WORD> {
+        Rx {
+            address: &self.spi.dr as *const _ as u32,
+            _spi: PhantomData,
+            _word: PhantomData,
+        }
+    }
+
+    /// Drops a stale received byte and clears the overrun flag
+    fn clear_rx(&self) {
+        // OVR is cleared by reading DR followed by SR
+        let _ = self.spi.dr.read();
+        let _ = self.spi.sr.read();
+    }
 }
 
-impl<SPI, PINS> spi::FullDuplex<u8> for Spi<SPI, PINS>
+/// DMA handle for the transmit direction of an SPI peripheral
+pub struct Tx<SPI, WORD = u8> {
+    address: u32,
+    _spi: PhantomData<SPI>,
+    _word: PhantomData<WORD>,
+}
+
+unsafe impl<SPI, WORD> PeriAddress for Tx<SPI, WORD> {
+    #[inline(always)]
+    fn address(&self) -> u32 {
+        self.address
+    }
+
+    type MemSize = WORD;
+}
+
+unsafe impl<SPI, WORD, STREAM, CHANNEL> DMASet<STREAM, CHANNEL, MemoryToPeripheral>
+    for Tx<SPI, WORD>
+where
+    SPI: DMASet<STREAM, CHANNEL, MemoryToPeripheral>,
+{
+}
+
+/// DMA handle for the receive direction of an SPI peripheral
+pub struct Rx<SPI, WORD = u8> {
+    address: u32,
+    _spi: PhantomData<SPI>,
+    _word: PhantomData<WORD>,
+}
+
+unsafe impl<SPI, WORD> PeriAddress for Rx<SPI, WORD> {
+    #[inline(always)]
+    fn address(&self) -> u32 {
+        self.address
+    }
+
+    type MemSize = WORD;
+}
+
+unsafe impl<SPI, WORD, STREAM, CHANNEL> DMASet<STREAM, CHANNEL, PeripheralToMemory>
+    for Rx<SPI, WORD>
+where
+    SPI: DMASet<STREAM, CHANNEL, PeripheralToMemory>,
+{
+}
+
+/// Full duplex DMA transfer started by [`Spi::transfer_dma`]
+///
+/// The transfer complete interrupt of the receive stream is enabled, it fires once the last byte
+/// has been received.
+pub struct DmaTransfer<SPI, PINS, TXSTREAM, TXCHANNEL, RXSTREAM, RXCHANNEL, TXBUF, RXBUF, WORD = u8>
+where
+    TXSTREAM: Stream,
+    RXSTREAM: Stream,
+{
+    spi: Spi<SPI, PINS, WORD>,
+    tx: Transfer<TXSTREAM, TXCHANNEL, Tx<SPI, WORD>, MemoryToPeripheral, TXBUF>,
+    rx: Transfer<RXSTREAM, RXCHANNEL, Rx<SPI, WORD>, PeripheralToMemory, RXBUF>,
+}
+
+impl<SPI, PINS, TXSTREAM, TXCHANNEL, RXSTREAM, RXCHANNEL, TXBUF, RXBUF, WORD>
+    DmaTransfer<SPI, PINS, TXSTREAM, TXCHANNEL, RXSTREAM, RXCHANNEL, TXBUF, RXBUF, WORD>
 where
     SPI: Deref<Target = spi1::RegisterBlock>,
+    TXSTREAM: Stream,
+    TXCHANNEL: Channel,
+    RXSTREAM: Stream,
+    RXCHANNEL: Channel,
+    Tx<SPI, WORD>: DMASet<TXSTREAM, TXCHANNEL, MemoryToPeripheral>,
+    Rx<SPI, WORD>: DMASet<RXSTREAM, RXCHANNEL, PeripheralToMemory>,
 {
-    type Error = Error;
+    /// Returns true once the last byte has been received
+    pub fn is_complete(&self) -> bool {
+        RXSTREAM::get_transfer_complete_flag()
+    }
 
-    fn read(&mut self) -> nb::Result<u8, Error> {
+    /// Waits for the transfer to complete and returns the resources
+    ///
+    /// With CRC enabled this also receives the CRC, check [`Spi::is_crcerr`] afterwards.
+    pub fn wait(self) -> (Spi<SPI, PINS, WORD>, TXSTREAM, RXSTREAM, TXBUF, RXBUF) {
+        while !self.is_complete() {}
+
+        // The DMA stream only receives the data, the CRC frame follows
+        if self.spi.spi.cr1.read().crcen().bit_is_set() {
+            self.spi.recv_crc();
+        }
+
+        self.spi
+            .spi
+            .cr2
+            .modify(|_, w| w.txdmaen().clear_bit().rxdmaen().clear_bit());
+
+        let (tx_stream, _, tx_buf, _) = self.tx.free();
+        let (rx_stream, _, rx_buf, _) = self.rx.free();
+        (self.spi, tx_stream, rx_stream, tx_buf, rx_buf)
+    }
+}
+
+/// Transmit only DMA transfer started by [`Spi::write_dma`]
+///
+/// The transfer complete interrupt of the stream is enabled, it fires once the last byte has been
+/// handed to the peripheral, which still has to shift it out.
+pub struct DmaWrite<SPI, PINS, STREAM, CHANNEL, BUF, WORD = u8>
+where
+    STREAM: Stream,
+{
+    spi: Spi<SPI, PINS, WORD>,
+    tx: Transfer<STREAM, CHANNEL, Tx<SPI, WORD>, MemoryToPeripheral, BUF>,
+}
+
+impl<SPI, PINS, STREAM, CHANNEL, BUF, WORD> DmaWrite<SPI, PINS, STREAM, CHANNEL, BUF, WORD>
+where
+    SPI: Deref<Target = spi1::RegisterBlock>,
+    STREAM: Stream,
+    CHANNEL: Channel,
+    Tx<SPI, WORD>: DMASet<STREAM, CHANNEL, MemoryToPeripheral>,
+{
+    /// Returns true once the last byte has been sent
+    pub fn is_complete(&self) -> bool {
+        let sr = self.spi.spi.sr.read();
+        STREAM::get_transfer_complete_flag() && sr.txe().bit_is_set() && sr.bsy().bit_is_clear()
+    }
+
+    /// Waits for the transfer to complete and returns the resources
+    ///
+    /// The bytes received during the transfer are dropped and the resulting overrun is cleared.
+    pub fn wait(self) -> (Spi<SPI, PINS, WORD>, STREAM, BUF) {
+        while !self.is_complete() {}
+
+        self.spi.spi.cr2.modify(|_, w| w.txdmaen().clear_bit());
+        self.spi.clear_rx();
+        // Nothing meaningful has been received, so the CRC check failing is expected
+        self.spi.spi.sr.modify(|_, w| w.crcerr().clear_bit());
+
+        let (stream, _, buf, _) = self.tx.free();
+        (self.spi, stream, buf)
+    }
+}
+
+impl<SPI, PINS, WORD> Spi<SPI, PINS, WORD>
+where
+    SPI: Deref<Target = spi1::RegisterBlock>,
+{
+    /// Returns `Ok` if a received frame can be read
+    fn check_read(&mut self) -> nb::Result<(), Error> {
         let sr = self.spi.sr.read();
 
         Err(if sr.ovr().bit_is_set() {
@@ -1060,15 +1797,14 @@ where
         } else if sr.crcerr().bit_is_set() {
             nb::Error::Other(Error::Crc)
         } else if sr.rxne().bit_is_set() {
-            // NOTE(read_volatile) read only 1 byte (the svd2rust API only allows
-            // reading a half-word)
-            return Ok(unsafe { ptr::read_volatile(&self.spi.dr as *const _ as *const u8) });
+            return Ok(());
         } else {
             nb::Error::WouldBlock
         })
     }
 
-    fn send(&mut self, byte: u8) -> nb::Result<(), Error> {
+    /// Returns `Ok` if a frame can be written
+    fn check_send(&mut self) -> nb::Result<(), Error> {
         let sr = self.spi.sr.read();
 
         Err(if sr.ovr().bit_is_set() {
@@ -1087,8 +1823,6 @@ where
             });
             nb::Error::Other(Error::Crc)
         } else if sr.txe().bit_is_set() {
-            // NOTE(write_volatile) see note above
-            unsafe { ptr::write_volatile(&self.spi.dr as *const _ as *mut u8, byte) }
             return Ok(());
         } else {
             nb::Error::WouldBlock
@@ -1096,6 +1830,95 @@ where
     }
 }
 
+impl<SPI, PINS> spi::FullDuplex<u8> for Spi<SPI, PINS, u8>
+where
+    SPI: Deref<Target = spi1::RegisterBlock>,
+{
+    type Error = Error;
+
+    fn read(&mut self) -> nb::Result<u8, Error> {
+        self.check_read()?;
+        // NOTE(read_volatile) read only 1 byte (the svd2rust API only allows
+        // reading a half-word)
+        Ok(unsafe { ptr::read_volatile(&self.spi.dr as *const _ as *const u8) })
+    }
+
+    fn send(&mut self, byte: u8) -> nb::Result<(), Error> {
+        self.check_send()?;
+        // NOTE(write_volatile) see note above
+        unsafe { ptr::write_volatile(&self.spi.dr as *const _ as *mut u8, byte) }
+        Ok(())
+    }
+}
+
+impl<SPI, PINS> spi::FullDuplex<u16> for Spi<SPI, PINS, u16>
+where
+    SPI: Deref<Target = spi1::RegisterBlock>,
+{
+    type Error = Error;
+
+    fn read(&mut self) -> nb::Result<u16, Error> {
+        self.check_read()?;
+        Ok(self.spi.dr.read().dr().bits())
+    }
+
+    fn send(&mut self, word: u16) -> nb::Result<(), Error> {
+        self.check_send()?;
+        self.spi.dr.write(|w| w.dr().bits(word));
+        Ok(())
+    }
+}
+
+impl<SPI, PINS, WORD> Spi<SPI, PINS, WORD>
+where
+    SPI: Deref<Target = spi1::RegisterBlock>,
+    WORD: Copy,
+    Self: spi::FullDuplex<WORD, Error = Error>,
+{
+    /// Exchanges `words` like a blocking transfer, followed by the CRC if it is enabled
+    ///
+    /// Returns [`Error::Crc`] if the received CRC doesn't match the calculated one.
+    pub fn transfer_with_crc(&mut self, words: &mut [WORD]) -> Result<(), Error> {
+        if words.is_empty() {
+            return Ok(());
+        }
+        self.reset_crc();
+
+        let last = words.len() - 1;
+        for (i, word) in words.iter_mut().enumerate() {
+            nb::block!(spi::FullDuplex::send(self, *word))?;
+            if i == last {
+                // The CRC is sent once the last frame has left the shift register
+                self.spi.cr1.modify(|_, w| w.crcnext().set_bit());
+            }
+            *word = nb::block!(spi::FullDuplex::read(self))?;
+        }
+
+        self.check_crc()
+    }
+
+    /// Sends `words` like a blocking write, followed by the CRC if it is enabled
+    pub fn write_with_crc(&mut self, words: &[WORD]) -> Result<(), Error> {
+        if words.is_empty() {
+            return Ok(());
+        }
+        self.reset_crc();
+
+        let last = words.len() - 1;
+        for (i, word) in words.iter().enumerate() {
+            nb::block!(spi::FullDuplex::send(self, *word))?;
+            if i == last {
+                self.spi.cr1.modify(|_, w| w.crcnext().set_bit());
+            }
+            nb::block!(spi::FullDuplex::read(self))?;
+        }
+
+        // Nothing meaningful has been received, so the CRC check failing is expected
+        let _ = self.check_crc();
+        Ok(())
+    }
+}
+
 impl<SPI, PINS> embedded_hal::blocking::spi::transfer::Default<u8> for Spi<SPI, PINS> where
     SPI: Deref<Target = spi1::RegisterBlock>
 {
@@ -1105,3 +1928,13 @@ impl<SPI, PINS> embedded_hal::blocking::spi::write::Default<u8> for Spi<SPI, PIN
     SPI: Deref<Target = spi1::RegisterBlock>
 {
 }
+
+impl<SPI, PINS> embedded_hal::blocking::spi::transfer::Default<u16> for Spi<SPI, PINS, u16> where
+    SPI: Deref<Target = spi1::RegisterBlock>
+{
+}
+
+impl<SPI, PINS> embedded_hal::blocking::spi::write::Default<u16> for Spi<SPI, PINS, u16> where
+    SPI: Deref<Target = spi1::RegisterBlock>
+{
+}
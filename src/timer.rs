@@ -103,6 +103,72 @@ pub enum Error {
     Disabled,
 }
 
+/// Source of the trigger output (TRGO), which drives slave timers, the ADC and the DAC
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum MasterMode {
+    /// Setting `UG` in `EGR`
+    Reset,
+    /// The counter enable signal
+    Enable,
+    /// The update event, i.e. once every period
+    Update,
+    /// A capture or compare match on channel 1
+    ComparePulse,
+    /// The output compare reference signal of channel 1
+    Oc1Ref,
+    /// The output compare reference signal of channel 2
+    Oc2Ref,
+    /// The output compare reference signal of channel 3
+    Oc3Ref,
+    /// The output compare reference signal of channel 4
+    Oc4Ref,
+}
+
+impl MasterMode {
+    fn bits(self) -> u32 {
+        match self {
+            MasterMode::Reset => 0b000,
+            MasterMode::Enable => 0b001,
+            MasterMode::Update => 0b010,
+            MasterMode::ComparePulse => 0b011,
+            MasterMode::Oc1Ref => 0b100,
+            MasterMode::Oc2Ref => 0b101,
+            MasterMode::Oc3Ref => 0b110,
+            MasterMode::Oc4Ref => 0b111,
+        }
+    }
+}
+
+/// Reaction of a slave timer to the trigger output of its master
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum SlaveMode {
+    /// The counter is reset on a rising edge of the trigger
+    Reset,
+    /// The counter counts while the trigger is high
+    Gated,
+    /// The counter is started on a rising edge of the trigger
+    Trigger,
+    /// The counter counts rising edges of the trigger instead of the timer clock
+    ExternalClock,
+}
+
+impl SlaveMode {
+    fn bits(self) -> u32 {
+        match self {
+            SlaveMode::Reset => 0b100,
+            SlaveMode::Gated => 0b101,
+            SlaveMode::Trigger => 0b110,
+            SlaveMode::ExternalClock => 0b111,
+        }
+    }
+}
+
+/// Master timer whose trigger output is connected to an internal trigger input (ITRx) of `SLAVE`
+pub trait InternalTrigger<SLAVE> {
+    /// Index of the internal trigger input
+    const ITR: u8;
+}
+
 impl Timer<SYST> {
     /// Configures the SYST clock as a periodic count down timer
     pub fn syst<T>(mut syst: SYST, timeout: T, clocks: Clocks) -> Self
@@ -454,6 +520,351 @@ hal! {
     TIM14: (tim14, 8, 8, apb1enr, apb1rstr, pclk1, ppre1),
 }
 
+macro_rules! master {
+    ($($TIM:ident,)+) => {
+        $(
+            impl Timer<$TIM> {
+                /// Selects the source of the trigger output (TRGO)
+                pub fn set_master_mode(&mut self, mode: MasterMode) {
+                    self.tim
+                        .cr2
+                        .modify(|r, w| unsafe { w.bits((r.bits() & !(0b111 << 4)) | (mode.bits() << 4)) });
+                }
+            }
+        )+
+    };
+}
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+macro_rules! basic_master {
+    ($($TIM:ident,)+) => {
+        $(
+            impl Timer<$TIM> {
+                /// Selects the source of the trigger output (TRGO)
+                ///
+                /// Panics if `mode` is not `Reset`, `Enable` or `Update`, basic timers have no
+                /// channels.
+                pub fn set_master_mode(&mut self, mode: MasterMode) {
+                    assert!(mode.bits() <= MasterMode::Update.bits());
+                    self.tim
+                        .cr2
+                        .modify(|r, w| unsafe { w.bits((r.bits() & !(0b111 << 4)) | (mode.bits() << 4)) });
+                }
+            }
+        )+
+    };
+}
+
+macro_rules! slave {
+    ($($TIM:ident,)+) => {
+        $(
+            impl Timer<$TIM> {
+                /// Synchronizes the timer to the trigger output of the master timer `M`
+                ///
+                /// In `Trigger` mode the counter has to be stopped with `cancel` first, it is
+                /// started by the trigger.
+                ///
+                /// ```ignore
+                /// let mut master = Timer::tim2(dp.TIM2, 1.khz(), clocks);
+                /// master.set_master_mode(MasterMode::Update);
+                /// let mut slave = Timer::tim3(dp.TIM3, 1.hz(), clocks);
+                /// // count the periods of TIM2
+                /// slave.set_slave_mode::<TIM2>(SlaveMode::ExternalClock);
+                /// ```
+                pub fn set_slave_mode<M>(&mut self, mode: SlaveMode)
+                where
+                    M: InternalTrigger<$TIM>,
+                {
+                    // TS = ITRx, SMS = mode
+                    self.tim.smcr.modify(|r, w| unsafe {
+                        w.bits((r.bits() & !0b111_0111) | ((M::ITR as u32) << 4) | mode.bits())
+                    });
+                }
+
+                /// Disables the slave mode, the counter runs from the timer clock again
+                pub fn disable_slave_mode(&mut self) {
+                    self.tim
+                        .smcr
+                        .modify(|r, w| unsafe { w.bits(r.bits() & !0b111) });
+                }
+            }
+        )+
+    };
+}
+
+macro_rules! internal_trigger {
+    ($($MASTER:ident => $SLAVE:ident: $itr:expr,)+) => {
+        $(
+            impl InternalTrigger<$SLAVE> for $MASTER {
+                const ITR: u8 = $itr;
+            }
+        )+
+    };
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+master!(TIM1, TIM5,);
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+master!(TIM2, TIM3, TIM4,);
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+master!(TIM8,);
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+basic_master!(TIM6,);
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+basic_master!(TIM7,);
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+slave!(TIM1, TIM5, TIM9,);
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+slave!(TIM2, TIM3, TIM4,);
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+slave!(TIM8, TIM12,);
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+internal_trigger!(
+    TIM5 => TIM1: 0,
+);
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+internal_trigger!(
+    TIM2 => TIM1: 1,
+    TIM3 => TIM1: 2,
+    TIM4 => TIM1: 3,
+
+    TIM1 => TIM2: 0,
+    TIM3 => TIM2: 2,
+    TIM4 => TIM2: 3,
+
+    TIM1 => TIM3: 0,
+    TIM2 => TIM3: 1,
+    TIM5 => TIM3: 2,
+    TIM4 => TIM3: 3,
+
+    TIM1 => TIM4: 0,
+    TIM2 => TIM4: 1,
+    TIM3 => TIM4: 2,
+
+    TIM2 => TIM5: 0,
+    TIM3 => TIM5: 1,
+    TIM4 => TIM5: 2,
+
+    TIM2 => TIM9: 0,
+    TIM3 => TIM9: 1,
+);
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+internal_trigger!(
+    TIM8 => TIM2: 1,
+    TIM8 => TIM4: 3,
+    TIM8 => TIM5: 3,
+
+    TIM1 => TIM8: 0,
+    TIM2 => TIM8: 1,
+    TIM4 => TIM8: 2,
+    TIM5 => TIM8: 3,
+
+    TIM4 => TIM12: 0,
+    TIM5 => TIM12: 1,
+);
+
 #[cfg(any(
     feature = "stm32f401",
     feature = "stm32f405",
@@ -634,6 +1045,12 @@ pub trait PinC2<TIM> {}
 pub trait PinC3<TIM> {}
 pub trait PinC4<TIM> {}
 
+// Complementary output channels and break input marker traits of the advanced-control timers
+pub trait PinC1N<TIM> {}
+pub trait PinC2N<TIM> {}
+pub trait PinC3N<TIM> {}
+pub trait PinBkin<TIM> {}
+
 macro_rules! channel_impl {
     ( $( $TIM:ident, $PINC:ident, $PINX:ident, $MODE:ident<$AF:ident>; )+ ) => {
         $(
@@ -803,3 +1220,103 @@ channel_impl!(
 
     TIM2, PinC4, PB2, Alternate<AF1>;
 );
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+channel_impl!(
+    TIM1, PinC1N, PA7, Alternate<AF1>;
+    TIM1, PinC1N, PB13, Alternate<AF1>;
+    TIM1, PinC2N, PB0, Alternate<AF1>;
+    TIM1, PinC2N, PB14, Alternate<AF1>;
+    TIM1, PinC3N, PB1, Alternate<AF1>;
+    TIM1, PinC3N, PB15, Alternate<AF1>;
+    TIM1, PinBkin, PA6, Alternate<AF1>;
+    TIM1, PinBkin, PB12, Alternate<AF1>;
+);
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+channel_impl!(
+    TIM1, PinC1N, PE8, Alternate<AF1>;
+    TIM1, PinC2N, PE10, Alternate<AF1>;
+    TIM1, PinC3N, PE12, Alternate<AF1>;
+    TIM1, PinBkin, PE15, Alternate<AF1>;
+);
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+channel_impl!(
+    TIM8, PinC1N, PA5, Alternate<AF3>;
+    TIM8, PinC1N, PA7, Alternate<AF3>;
+    TIM8, PinC2N, PB0, Alternate<AF3>;
+    TIM8, PinC2N, PB14, Alternate<AF3>;
+    TIM8, PinC3N, PB1, Alternate<AF3>;
+    TIM8, PinC3N, PB15, Alternate<AF3>;
+    TIM8, PinBkin, PA6, Alternate<AF3>;
+);
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+channel_impl!(
+    TIM8, PinC1N, PH13, Alternate<AF3>;
+    TIM8, PinC2N, PH14, Alternate<AF3>;
+    TIM8, PinC3N, PH15, Alternate<AF3>;
+    TIM8, PinBkin, PI4, Alternate<AF3>;
+);
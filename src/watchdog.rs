@@ -1,8 +1,10 @@
 //! Watchdog peripherals
 
 use crate::{
+    bb,
     hal::watchdog::{Watchdog, WatchdogEnable},
-    stm32::{DBGMCU, IWDG},
+    rcc::Clocks,
+    stm32::{DBGMCU, IWDG, RCC, WWDG},
     time::MilliSeconds,
 };
 
@@ -102,3 +104,155 @@ impl Watchdog for IndependentWatchdog {
         self.iwdg.kr.write(|w| unsafe { w.key().bits(KR_RELOAD) });
     }
 }
+
+/// Window watchdog errors
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Error {
+    /// The refresh window hasn't been reached yet, refreshing would reset the MCU
+    TooEarly,
+}
+
+/// Wraps the Window Watchdog (WWDG) peripheral
+///
+/// The watchdog counts down from the reload value and resets the MCU when the timeout expires. A
+/// window set with [`set_window`](Self::set_window) also resets the MCU if the watchdog is fed
+/// before the window opens, [`try_feed`](Self::try_feed) checks for that instead.
+pub struct WindowWatchdog {
+    wwdg: WWDG,
+    pclk1: u32,
+    /// Counter value written on every refresh
+    reload: u8,
+}
+
+const WWDG_MAX_TB: u8 = 0b11;
+/// The MCU is reset when the counter drops below this value
+const WWDG_MIN_T: u8 = 0x40;
+const WWDG_MAX_T: u8 = 0x7F;
+
+impl WindowWatchdog {
+    /// Wrap the watchdog, it is started by `start`
+    pub fn new(wwdg: WWDG, clocks: Clocks) -> Self {
+        unsafe {
+            const EN_BIT: u8 = 11;
+
+            // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+            let rcc = &(*RCC::ptr());
+
+            bb::set(&rcc.apb1enr, EN_BIT);
+
+            // Stall the pipeline to work around erratum 2.1.13 (DM00037591)
+            cortex_m::asm::dsb();
+        }
+
+        WindowWatchdog {
+            wwdg,
+            pclk1: clocks.pclk1().0,
+            reload: WWDG_MAX_T,
+        }
+    }
+
+    /// Debug window watchdog stopped when core is halted
+    pub fn stop_on_debug(&self, dbgmcu: &DBGMCU, stop: bool) {
+        dbgmcu.apb1_fz.modify(|_, w| w.dbg_wwdg_stop().bit(stop));
+    }
+
+    /// Only allow feeding the watchdog during the last `window` before the timeout
+    ///
+    /// The window is rounded down to a whole number of counter steps, but spans at least one step
+    /// so the watchdog can still be fed. It depends on the timeout, so this has to be called after
+    /// `start`, which opens the window fully.
+    pub fn set_window<T: Into<MilliSeconds>>(&mut self, window: T) {
+        let tb = self.wwdg.cfr.read().wdgtb().bits();
+        let steps =
+            (u64::from(window.into().0) * u64::from(self.pclk1) / 1000 / Self::step_cycles(tb))
+                .max(1);
+        let limit = (u64::from(WWDG_MIN_T) - 1 + steps).min(u64::from(self.reload)) as u8;
+
+        self.wwdg.cfr.modify(|_, w| w.w().bits(limit));
+    }
+
+    /// Enables the early wakeup interrupt, which fires one counter step before the reset
+    ///
+    /// The interrupt can only be disabled by a reset. It has to be cleared with
+    /// `clear_early_wakeup`, feeding the watchdog from the interrupt handler prevents the reset.
+    pub fn listen_early_wakeup(&mut self) {
+        self.wwdg.cfr.modify(|_, w| w.ewi().set_bit());
+    }
+
+    /// Returns true if the counter reached the early wakeup value
+    pub fn is_early_wakeup(&self) -> bool {
+        self.wwdg.sr.read().ewif().bit_is_set()
+    }
+
+    /// Clears the early wakeup flag
+    pub fn clear_early_wakeup(&mut self) {
+        self.wwdg.sr.modify(|_, w| w.ewif().clear_bit());
+    }
+
+    /// Returns true if feeding the watchdog now wouldn't reset the MCU
+    pub fn is_window_open(&self) -> bool {
+        self.wwdg.cr.read().t().bits() <= self.wwdg.cfr.read().w().bits()
+    }
+
+    /// Feeds the watchdog if the window is open
+    pub fn try_feed(&mut self) -> Result<(), Error> {
+        if self.is_window_open() {
+            self.feed();
+            Ok(())
+        } else {
+            Err(Error::TooEarly)
+        }
+    }
+
+    /// Returns the interval in ms
+    pub fn interval(&self) -> MilliSeconds {
+        let tb = self.wwdg.cfr.read().wdgtb().bits();
+        let steps = u64::from(self.reload - WWDG_MIN_T + 1);
+        MilliSeconds((steps * Self::step_cycles(tb) * 1000 / u64::from(self.pclk1)) as u32)
+    }
+
+    /// APB1 clock cycles per counter step for the timer base `tb`
+    fn step_cycles(tb: u8) -> u64 {
+        4096 << tb
+    }
+
+    fn setup(&mut self, timeout_ms: u32) {
+        let cycles = u64::from(timeout_ms) * u64::from(self.pclk1) / 1000;
+        let max_steps = u64::from(WWDG_MAX_T - WWDG_MIN_T + 1);
+
+        let mut tb = 0;
+        while tb < WWDG_MAX_TB && cycles / Self::step_cycles(tb) > max_steps {
+            tb += 1;
+        }
+
+        let steps = (cycles / Self::step_cycles(tb)).max(1).min(max_steps) as u8;
+        self.reload = WWDG_MIN_T - 1 + steps;
+
+        self.wwdg
+            .cfr
+            .modify(|_, w| w.wdgtb().bits(tb).w().bits(WWDG_MAX_T));
+    }
+}
+
+impl WatchdogEnable for WindowWatchdog {
+    type Time = MilliSeconds;
+
+    /// Starts the watchdog with the window fully open
+    ///
+    /// The watchdog can't be stopped again. `period` is limited by the APB1 clock, at 42 MHz the
+    /// longest timeout is about 50 ms.
+    fn start<T: Into<Self::Time>>(&mut self, period: T) {
+        self.setup(period.into().0);
+
+        let t = self.reload;
+        self.wwdg.cr.write(|w| w.t().bits(t).wdga().set_bit());
+    }
+}
+
+impl Watchdog for WindowWatchdog {
+    /// Feeds the watchdog, this resets the MCU if the window isn't open yet
+    fn feed(&mut self) {
+        let t = self.reload;
+        self.wwdg.cr.write(|w| w.t().bits(t).wdga().set_bit());
+    }
+}